@@ -19,24 +19,38 @@
 //!
 //!
 
-use crate::{CustomWrapper, FinalizeProjection, Marker, Projectable, ProjectableMarker};
+use crate::lens::LensTarget;
+use crate::{FinalizeProjection, Marker, Projectable, ProjectableMarker, UnwrapTarget, WrapMarker};
 use atomic::Atomic;
-use core::mem::transmute_copy;
+use core::mem::{transmute_copy, ManuallyDrop};
 
-unsafe impl<'a, T> CustomWrapper for &'a Atomic<T> {
-    type Output = Whatever<&'a Atomic<T>>;
+unsafe impl<'a> WrapMarker<Atomic<()>> for Marker<&'a ()> {
+    type Output = Marker<&'a Atomic<()>>;
+
+    fn wrap_marker(self) -> Self::Output {
+        Marker::new()
+    }
 }
+impl<T, M: WrapMarker<Atomic<()>>> UnwrapTarget for &ManuallyDrop<(*mut Atomic<T>, M)> {
+    type Target = T;
+    type Marker = M::Output;
 
-// transparent wrapper to work around orphan rules
-#[repr(transparent)]
-pub struct Whatever<T>(T);
+    unsafe fn unwrap_target(&self) -> (*mut Self::Target, Self::Marker) {
+        let (ptr, marker): (*mut Atomic<T>, M) = transmute_copy(*self);
+        (ptr as *mut T, marker.wrap_marker())
+    }
+}
 
-unsafe impl<'a, T> Projectable for Whatever<&'a Atomic<T>> {
-    type Target = T;
-    type Marker = Marker<&'a Atomic<()>>;
+unsafe impl<P, T> LensTarget<T, Atomic<()>> for P
+where
+    P: Projectable<Target = Atomic<T>>,
+    P::Marker: WrapMarker<Atomic<()>>,
+{
+    type Marker = <P::Marker as WrapMarker<Atomic<()>>>::Output;
 
-    fn get_raw(&self) -> (*mut Self::Target, Self::Marker) {
-        (self.0 as *const _ as *const T as _, Marker::new())
+    fn get_raw_outer(&self) -> (*mut T, Self::Marker) {
+        let (ptr, marker) = self.get_raw();
+        (ptr as *mut T, marker.wrap_marker())
     }
 }
 
@@ -1,6 +1,6 @@
 use crate::{
-    project, CustomWrapper, DropLeftovers, Marker, Owned, OwningMarker, Projectable,
-    ProjectableMarker,
+    project, CustomWrapper, DropLeftovers, FieldOf, Marker, Owned, OwningMarker, Projectable,
+    ProjectableMarker, SupportsEnums,
 };
 use alloc::boxed::Box;
 use core::marker::PhantomData;
@@ -96,6 +96,17 @@ impl<T, M: ProjectableMarker<T>> GenericPointer<T, M> {
     }
 }
 
+impl<T, M> GenericPointer<T, M> {
+    /// Projects to the field `F`, pointer kind stays the same.
+    pub fn field<F: FieldOf<T>>(self) -> GenericPointer<F::Type, M> {
+        GenericPointer {
+            ptr: (self.ptr as *mut u8).wrapping_add(F::lens().offset()) as _,
+            ty: PhantomData,
+            marker: self.marker,
+        }
+    }
+}
+
 unsafe impl<T, M> CustomWrapper for GenericPointer<T, M> {
     type Output = GenericPointer<T, M>;
 }
@@ -110,6 +121,7 @@ unsafe impl<T, M> Projectable for GenericPointer<T, M> {
 }
 
 pub struct GenericMarker<M>(M);
+unsafe impl<M: SupportsEnums> SupportsEnums for GenericMarker<M> {}
 
 impl<T, M> ProjectableMarker<T> for GenericMarker<M>
 where
@@ -134,7 +146,7 @@ macro_rules! generic_ptr {
             let var = core::mem::ManuallyDrop::new($val);
             let var = {
                 use $crate::Preprocess;
-                core::mem::ManuallyDrop::new((&&&&&var).preprocess())
+                core::mem::ManuallyDrop::new((&&&&&&var).preprocess())
             };
 
             let (ptr, marker) = {
@@ -148,7 +160,7 @@ macro_rules! generic_ptr {
         let var = core::mem::ManuallyDrop::new($val);
         let var = unsafe {
             use $crate::Preprocess;
-            core::mem::ManuallyDrop::new((&&&&&var).preprocess())
+            core::mem::ManuallyDrop::new((&&&&&&var).preprocess())
         };
 
         let $name = unsafe {
@@ -0,0 +1,343 @@
+//! Field lenses, i.e. "which field" as a runtime value.
+//!
+//! ```rust
+//! # use std::cell::Cell;
+//! # use std::marker::PhantomPinned;
+//! # use std::pin::Pin;
+//! use projecture::{lens, pin_projectable, Lens};
+//! struct Foo {
+//!     a: Bar,
+//!     _p: PhantomPinned,
+//! }
+//! struct Bar {
+//!     b: [Baz; 4],
+//! }
+//! #[derive(Clone, Copy)]
+//! struct Baz {
+//!     c: usize,
+//! }
+//! pin_projectable! { Foo }
+//!
+//! let c: Lens<Foo, usize> = lens!(Foo => a.b[3].c);
+//! // same as above
+//! let c = lens!(Foo => a.b).then(lens!([Baz; 4] => [3])).then(lens!(Baz => c));
+//!
+//! let mut foo = Box::pin(Foo { a: Bar { b: [Baz { c: 0 }; 4] }, _p: PhantomPinned });
+//! let x: Pin<&mut usize> = c.project(foo.as_mut());
+//! *x.get_mut() += 1;
+//! let x: &usize = c.project(foo.as_ref().get_ref());
+//! assert_eq!(*x, 1);
+//!
+//! let cell = Cell::new(Baz { c: 1 });
+//! let x: &Cell<usize> = lens!(Baz => c).project(&cell);
+//! x.set(2);
+//! assert_eq!(cell.get().c, 2);
+//! ```
+
+use crate::{CustomWrapper, Projectable, ProjectableMarker, ProjectionIndex, WrapMarker};
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::mem::{align_of, size_of, transmute_copy, ManuallyDrop, MaybeUninit};
+
+/// Offset of the `Inner` field in the `Outer` type, see [`lens!`](crate::lens!) to create it.
+///
+/// It can be applied to any pointer to `Outer` that [`project!`](crate::project) can project
+/// with [`Lens::project`], as well as to `&Cell<Outer>`, `&mut MaybeUninit<Outer>` and other pointers
+/// to transparent wrappers that `project!` looks through.
+pub struct Lens<Outer, Inner> {
+    offset: usize,
+    phantom: PhantomData<fn(*mut Outer) -> *mut Inner>,
+}
+
+impl<Outer, Inner> Clone for Lens<Outer, Inner> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<Outer, Inner> Copy for Lens<Outer, Inner> {}
+
+impl<Outer, Inner> Lens<Outer, Inner> {
+    /// Creates a lens from an offset of the field.
+    ///
+    /// # Safety
+    /// There must be a properly aligned `Inner` field at `offset` in any `Outer` value.
+    pub unsafe fn from_offset(offset: usize) -> Self {
+        Lens {
+            offset,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Offset of the field in bytes
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Composes this lens with the lens to a field of `Inner`
+    pub fn then<Field>(self, other: Lens<Inner, Field>) -> Lens<Outer, Field> {
+        Lens {
+            offset: self.offset + other.offset,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Projects `ptr` to the field.
+    ///
+    /// `Kind` is inferred from the pointer type and only shows up in generic code as a bound `P::Output: LensTarget<Outer, Kind>`.
+    pub fn project<P, Kind>(
+        &self,
+        ptr: P,
+    ) -> <<P::Output as LensTarget<Outer, Kind>>::Marker as ProjectableMarker<Inner>>::Output
+    where
+        P: CustomWrapper,
+        P::Output: LensTarget<Outer, Kind>,
+        <P::Output as LensTarget<Outer, Kind>>::Marker: ProjectableMarker<Inner>,
+    {
+        let var = ManuallyDrop::new(ptr);
+        let var = ManuallyDrop::new(unsafe { transmute_copy::<_, P::Output>(&var) });
+        let (ptr, marker) = var.get_raw_outer();
+        let field = (ptr as *mut u8).wrapping_add(self.offset) as *mut Inner;
+        unsafe { marker.from_raw(field) }
+    }
+}
+
+#[doc(hidden)]
+pub fn field_lens<Outer, Inner>(base: *mut Outer, field: *mut Inner) -> Lens<Outer, Inner> {
+    let offset = field as usize - base as usize;
+    assert!(
+        offset + size_of::<Inner>() <= size_of::<Outer>(),
+        "lens must point to a field"
+    );
+    assert!(
+        offset & (align_of::<Inner>() - 1) == 0 && align_of::<Inner>() <= align_of::<Outer>(),
+        "lens can't point to an unaligned field of a `#[repr(packed)]` struct"
+    );
+    unsafe { Lens::from_offset(offset) }
+}
+
+#[doc(hidden)]
+pub fn check_not_union<T: ?Sized, F: FnOnce(&T)>(_ptr: *mut T, _field: F) {}
+
+#[doc(hidden)]
+pub fn array_item<T, const N: usize>(ptr: *mut [T; N], index: usize) -> *mut T {
+    index.index_raw(ptr as *mut T, || N, true)
+}
+
+/// Type that names `N`-th field of `T`, see [`FieldOf`].
+pub struct Field<T, const N: usize>(PhantomData<fn() -> T>);
+
+/// Field of `T` as a type, so generic code can be parameterized by which field it works with.
+///
+/// Implemented for [`Field`] by [`FieldOf!`](macro@crate::FieldOf) derive,
+/// use [`Lens::project`] on [`FieldOf::lens`] to project any kind of pointer
+/// or [`GenericPointer::field`](crate::generic::GenericPointer::field) in generic code.
+/// ```rust
+/// use projecture::{Field, FieldOf};
+/// use macro_rules_attribute::derive;
+/// #[derive(FieldOf!)]
+/// struct Person {
+///     name: &'static str,
+///     age: u32,
+/// }
+///
+/// fn sort_by<F: FieldOf<Person>>(people: &mut [Person])
+/// where
+///     F::Type: Ord,
+/// {
+///     people.sort_by(|a, b| F::lens().project(a).cmp(F::lens().project(b)));
+/// }
+/// type Age = Field<Person, 1>;
+///
+/// let mut people = [Person { name: "b", age: 10 }, Person { name: "a", age: 20 }];
+/// sort_by::<Age>(&mut people);
+/// assert_eq!(people[0].name, "b");
+/// sort_by::<Field<Person, 0>>(&mut people);
+/// assert_eq!(people[0].name, "a");
+/// assert_eq!(Age::NAME, "age");
+/// ```
+pub trait FieldOf<T> {
+    /// Type of the field
+    type Type;
+    /// Name of the field, or its index for tuple structs
+    const NAME: &'static str;
+    /// Lens to the field
+    fn lens() -> Lens<T, Self::Type>;
+}
+
+/// Projectable pointer to `Outer`, possibly wrapped in a transparent wrapper like `Cell`.
+///
+/// `Kind` is a wrapper with `()` inside, or just `()` if there is no wrapper.
+///
+/// # Safety
+/// `get_raw_outer` must return a pointer to an `Outer` value, so that pointer to any of its fields
+/// can be passed to `ProjectableMarker::from_raw` of the returned marker.
+/// If `Outer` is wrapped, marker must wrap the field in the same way, e.g. give `&Cell<Field>` for `&Cell<Outer>`.
+pub unsafe trait LensTarget<Outer, Kind> {
+    type Marker;
+    fn get_raw_outer(&self) -> (*mut Outer, Self::Marker);
+}
+
+unsafe impl<P: Projectable<Target = Outer>, Outer> LensTarget<Outer, ()> for P {
+    type Marker = P::Marker;
+
+    fn get_raw_outer(&self) -> (*mut Outer, Self::Marker) {
+        self.get_raw()
+    }
+}
+unsafe impl<P, Outer> LensTarget<Outer, Cell<()>> for P
+where
+    P: Projectable<Target = Cell<Outer>>,
+    P::Marker: WrapMarker<Cell<()>>,
+{
+    type Marker = <P::Marker as WrapMarker<Cell<()>>>::Output;
+
+    fn get_raw_outer(&self) -> (*mut Outer, Self::Marker) {
+        let (ptr, marker) = self.get_raw();
+        (ptr as *mut Outer, marker.wrap_marker())
+    }
+}
+unsafe impl<P, Outer> LensTarget<Outer, MaybeUninit<()>> for P
+where
+    P: Projectable<Target = MaybeUninit<Outer>>,
+    P::Marker: WrapMarker<MaybeUninit<()>>,
+{
+    type Marker = <P::Marker as WrapMarker<MaybeUninit<()>>>::Output;
+
+    fn get_raw_outer(&self) -> (*mut Outer, Self::Marker) {
+        let (ptr, marker) = self.get_raw();
+        (ptr as *mut Outer, marker.wrap_marker())
+    }
+}
+
+/// Creates [`Lens`] to a field, `lens!(Foo => a.b[3].c)`.
+///
+/// Path can contain fields and constant indexes into arrays. Just like with [`offset_of!`](crate::offset_of),
+/// nested tuple fields need a space like `lens!(Foo => c.1 .1)`, and path can't go through `Deref`.
+/// ```rust,compile_fail
+/// # use projecture::lens;
+/// struct Foo {
+///     b: Box<(usize, usize)>,
+/// }
+/// let lens = lens!(Foo => b.0);
+/// ```
+/// Fields of unions can't be projected, because the field that is projected might not be the active one.
+/// ```rust,compile_fail
+/// # use projecture::lens;
+/// union Foo {
+///     a: u8,
+///     b: bool,
+/// }
+/// let lens = lens!(Foo => b);
+/// ```
+/// Index out of bounds panics
+/// ```rust,should_panic
+/// # use projecture::lens;
+/// let lens = lens!([u8; 4] => [4]);
+/// ```
+#[macro_export]
+macro_rules! lens {
+    ($type:ty => $($path:tt)+) => {{
+        let mut uninit = core::mem::MaybeUninit::<$type>::uninit();
+        let base = uninit.as_mut_ptr();
+        // only pointer arithmetic on the uninitialized value, nothing is read from it
+        let field = $crate::lens_path!(base $($path)+);
+        $crate::lens::field_lens(base, field)
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! lens_path {
+    ($ptr:ident [ $index:expr ] $($tail:tt)*) => {{
+        let ptr = $crate::lens::array_item($ptr, $index);
+        $crate::lens_path!(ptr $($tail)*)
+    }};
+    ($ptr:ident . $field:tt $($tail:tt)*) => {
+        $crate::lens_path!($ptr $field $($tail)*)
+    };
+    ($ptr:ident $field:tt $($tail:tt)*) => {{
+        let ptr = {
+            use $crate::CheckNoDeref;
+            (&&$ptr).check_deref()
+        };
+        // fields of unions can't be accessed outside of `unsafe`, so they fail to compile here
+        $crate::lens::check_not_union(ptr, |outer| {
+            let _ = &outer.$field;
+        });
+        #[allow(unused_unsafe)]
+        let ptr = unsafe { core::ptr::addr_of_mut!((*ptr).$field) };
+        $crate::lens_path!(ptr $($tail)*)
+    }};
+    ($ptr:ident) => {
+        $ptr
+    };
+}
+
+/// Derive macro for [`macro_rules_attribute`](https://docs.rs/macro_rules_attribute)
+/// that implements [`FieldOf`] for [`Field`]s of a struct.
+#[macro_export]
+#[allow(non_snake_case)]
+macro_rules! FieldOf {
+    ( [$($head:tt)*] { $($inner:tt)* } ) => {
+        $crate::parse_generics!{ [field_of_impl named { $($inner)* }] $($head)* }
+    };
+    ( [$($head:tt)*] ($($inner:tt)*) ; ) => {
+        $crate::parse_generics!{ [field_of_impl tuple ( $($inner)* )] $($head)* }
+    };
+    // where clause of a tuple struct
+    ( [$($head:tt)*] ($($inner:tt)*) [$($where:tt)*] ; ) => {
+        $crate::parse_generics!{ [field_of_impl tuple ( $($inner)* )] $($head)* $($where)* }
+    };
+    ( [$($head:tt)*] ($($inner:tt)*) [$($where:tt)*] $token:tt $($tail:tt)+ ) => {
+        $crate::FieldOf!{ [$($head)*] ($($inner)*) [$($where)* $token] $($tail)+ }
+    };
+    ( [$($head:tt)*] ($($inner:tt)*) where $($tail:tt)+ ) => {
+        $crate::FieldOf!{ [$($head)*] ($($inner)*) [where] $($tail)+ }
+    };
+    ( [$($head:tt)*] ; ) => {
+        $crate::parse_generics!{ [field_of_impl named {}] $($head)* }
+    };
+    ( [$($head:tt)*] $token:tt $($tail:tt)* ) => { $crate::FieldOf!{ [$($head)* $token] $($tail)* } };
+    ( $(#[$attr:meta])* $vis:vis struct $($tail:tt)* ) => { $crate::FieldOf!{ [] $($tail)* } };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! field_of_impl {
+    ([named { $($(#[$attr:meta])* $vis:vis $field:ident : $type:ty),* $(,)? }] $generics:tt $self:tt $where:tt) => {
+        $crate::with_tuple_indexes! { field_of_impl [fields [$generics $self $where] [$(($field $type))*]] }
+    };
+    ([tuple ( $($(#[$attr:meta])* $vis:vis $type:ty),* $(,)? )] $generics:tt $self:tt $where:tt) => {
+        $crate::with_tuple_indexes! { field_of_impl [tuple [$generics $self $where] [$($type),*]] }
+    };
+    (tuple $args:tt [$($type:ty),*] [$($idx:tt)*]) => {
+        $crate::field_of_impl! { zip tuple $args [] [$($type),*] [$($idx)*] }
+    };
+    (zip tuple $args:tt [$($fields:tt)*] [$type:ty $(, $rest:ty)*] [$idx:tt $($indexes:tt)*]) => {
+        $crate::field_of_impl! { zip tuple $args [$($fields)* ($idx $type)] [$($rest),*] [$($indexes)*] }
+    };
+    (zip tuple $args:tt [$($fields:tt)*] [] [$($indexes:tt)*]) => {
+        $crate::with_tuple_indexes! { field_of_impl [fields $args [$($fields)*]] }
+    };
+    (zip tuple $args:tt [$($fields:tt)*] [$($rest:ty),*] []) => {
+        compile_error!("`FieldOf` supports only tuple structs with up to 256 fields");
+    };
+    (fields $args:tt [($field:tt $type:ty) $($rest:tt)*] [$idx:tt $($indexes:tt)*]) => {
+        $crate::field_of_impl! { field $args $field $type, $idx }
+        $crate::field_of_impl! { fields $args [$($rest)*] [$($indexes)*] }
+    };
+    (fields $args:tt [] [$($indexes:tt)*]) => {};
+    (fields $args:tt [$($rest:tt)+] []) => {
+        compile_error!("`FieldOf` supports only structs with up to 256 fields");
+    };
+    (field [[$($generics:tt)*] [$($self:tt)+] [$($where:tt)*]] $field:tt $type:ty, $idx:tt) => {
+        impl<$($generics)*> $crate::FieldOf<$($self)+> for $crate::Field<$($self)+, $idx> where $($where)* {
+            type Type = $type;
+            const NAME: &'static str = stringify!($field);
+
+            fn lens() -> $crate::Lens<$($self)+, $type> {
+                $crate::lens!($($self)+ => $field)
+            }
+        }
+    };
+}
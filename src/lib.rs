@@ -14,7 +14,9 @@ use core::cell::{Cell, UnsafeCell};
 use core::convert::Infallible;
 use core::marker::{PhantomData, PhantomPinned};
 use core::mem::{transmute_copy, ManuallyDrop, MaybeUninit};
-use core::ops::{Deref, DerefMut};
+use core::ops::{
+    Deref, DerefMut, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive,
+};
 use core::pin::Pin;
 use core::ptr;
 use core::ptr::{drop_in_place, null_mut, NonNull};
@@ -32,11 +34,18 @@ pub mod pin;
 // pub use memoffset::*;
 
 pub use option::OptionMarker;
+#[doc(hidden)]
+pub use option::OptionalChain;
 mod option;
 mod refcell;
 
 pub mod generic;
 
+use lens::LensTarget;
+pub use lens::{Field, FieldOf, Lens};
+/// Field lenses
+pub mod lens;
+
 // helper to wrap `T` `&T` and `&mut T` to prevent conflicting implementations when doing autoderef specialization
 #[doc(hidden)]
 pub unsafe trait Preprocess {
@@ -47,15 +56,21 @@ pub unsafe trait Preprocess {
 // ///Implement this on your reference type that you want to work with this crate (like `Pin` or `std::cell:Ref`)
 // pub unsafe trait MarkerNonOwned {}
 
-// wrapper to prevent overlapping implementations
+// wrapper to prevent overlapping implementations,
+// `UnsafeCell` allows fields that are not moved out to be dropped in place through `get_raw`
 #[doc(hidden)]
 #[repr(transparent)]
-pub struct Owned<T>(ManuallyDrop<T>);
+pub struct Owned<T>(UnsafeCell<ManuallyDrop<T>>);
+impl<T> Owned<T> {
+    fn new(value: ManuallyDrop<T>) -> Self {
+        Owned(UnsafeCell::new(value))
+    }
+}
 unsafe impl<T> Preprocess for ManuallyDrop<T> {
     type Output = Owned<T>;
 
     fn preprocess(&self) -> Self::Output {
-        unsafe { transmute_copy(self) }
+        Owned::new(unsafe { ptr::read(self) })
     }
 }
 
@@ -80,53 +95,37 @@ impl<T: Deref> Deref for Helper<T> {
         self.0.deref()
     }
 }
-unsafe impl<'a, T> Preprocess for &&ManuallyDrop<&'a T> {
-    type Output = Helper<&'a T>;
-
-    fn preprocess(&self) -> Self::Output {
-        unsafe { transmute_copy(**self) }
-    }
-}
-
-unsafe impl<'a, T> Preprocess for &&ManuallyDrop<&'a mut T> {
-    type Output = Helper<&'a mut T>;
+// owned `Option` of something that is not a pointer
+unsafe impl<T> Preprocess for &ManuallyDrop<Option<T>> {
+    type Output = Option<Owned<T>>;
 
     fn preprocess(&self) -> Self::Output {
-        unsafe { transmute_copy(**self) }
+        // `UnsafeCell` hides the niche, so the layout is different from `Option<T>`
+        let value = unsafe { ptr::read(&***self) };
+        value.map(|value| Owned::new(ManuallyDrop::new(value)))
     }
 }
 
-///Trait to, if necessary, transparently wrap type to prevent conflicting implementations
+/// Trait to, if necessary, transparently wrap type to prevent conflicting implementations.
+///
+/// Each pointer kind implements it once, wrapper kinds like `Option` and `Pin` implement it
+/// in terms of the pointer they wrap, so arbitrarily nested pointers are wrapped recursively.
 pub unsafe trait CustomWrapper {
     /// `Self` but wrapped in `#[repr(transparent)]` wrapper,
     /// or just `Self` if there is no problems with conflicting implementations
     type Output;
 }
-unsafe impl<T: CustomWrapper> Preprocess for &&&ManuallyDrop<T> {
-    type Output = T::Output;
-
-    fn preprocess(&self) -> Self::Output {
-        unsafe { transmute_copy(***self) }
-    }
+unsafe impl<'a, T: ?Sized> CustomWrapper for &'a T {
+    type Output = Helper<&'a T>;
 }
-unsafe impl<'a, T> Preprocess for &&&&'a ManuallyDrop<T>
-where
-    &'a T: CustomWrapper,
-{
-    type Output = <&'a T as CustomWrapper>::Output;
-
-    fn preprocess(&self) -> Self::Output {
-        unsafe { transmute_copy(****self) }
-    }
+unsafe impl<'a, T: ?Sized> CustomWrapper for &'a mut T {
+    type Output = Helper<&'a mut T>;
 }
-unsafe impl<'a, 'b, T> Preprocess for &&&&'a &'b ManuallyDrop<T>
-where
-    &'a &'b T: CustomWrapper,
-{
-    type Output = <&'a &'b T as CustomWrapper>::Output;
+unsafe impl<T: CustomWrapper> Preprocess for &&ManuallyDrop<T> {
+    type Output = T::Output;
 
     fn preprocess(&self) -> Self::Output {
-        unsafe { transmute_copy(*****self) }
+        unsafe { transmute_copy(**self) }
     }
 }
 
@@ -187,7 +186,7 @@ where
 /// Trait to get raw pointer to underlying struct
 pub unsafe trait Projectable {
     /// Inner type to which projection will be applied
-    type Target;
+    type Target: ?Sized;
     /// Marker type to track information about the type of projection being done
     /// Should implement `ProjectableMarker`
     type Marker;
@@ -197,9 +196,6 @@ pub unsafe trait Projectable {
 }
 
 /// Trait to wrap raw pointer to a field with a type that corresponds to a projection being done.
-///
-/// Marker also must have an inherent empty `pub fn check(&self){}` function which is used to check that `project!`
-/// macro works on concrete types, and not on generics, you can look at [`Marker::check`] as an example.
 pub trait ProjectableMarker<T: ?Sized> {
     /// Wrapped pointer type
     type Output;
@@ -225,6 +221,55 @@ pub unsafe trait DerefProjectable {
     }
 }
 
+/// Implemented by pointer kinds that can be found in the target of another pointer,
+/// so `project!` can deref both of them at once, e.g. `RefMut<'_, Option<&mut T>>` or `Ref<'_, Pin<Box<T>>>`.
+/// Implementations for wrappers like `Option` and `Pin` delegate to the wrapped pointer,
+/// so any nesting of them is peeled recursively.
+///
+/// `M` is the marker of the outer pointer, the resulting marker keeps it
+/// unless the inner pointer gives less access than the outer one, like `&T` inside of `RefMut`.
+///
+/// # Safety
+/// `deref_chain` must return a pointer that can be accessed as described by the returned marker,
+/// or null if the returned marker is an `OptionMarker` with `None` inside.
+pub unsafe trait DerefChain<M> {
+    type Target: ?Sized;
+    type Marker;
+
+    /// # Safety
+    /// `this` must point to a valid value that can be accessed as described by `outer`
+    /// for as long as the resulting pointer is used.
+    unsafe fn deref_chain(this: *mut Self, outer: M) -> (*mut Self::Target, Self::Marker);
+}
+
+unsafe impl<T: ?Sized, M> DerefChain<M> for &mut T {
+    type Target = T;
+    type Marker = M;
+
+    unsafe fn deref_chain(this: *mut Self, outer: M) -> (*mut T, M) {
+        (ptr::read(this as *const *mut T), outer)
+    }
+}
+
+unsafe impl<'b, T: ?Sized, M> DerefChain<M> for &'b T {
+    type Target = T;
+    type Marker = Marker<&'b ()>;
+
+    unsafe fn deref_chain(this: *mut Self, _outer: M) -> (*mut T, Self::Marker) {
+        (ptr::read(this as *const *const T) as *mut T, Marker::new())
+    }
+}
+
+unsafe impl<T, M> DerefChain<M> for Box<T> {
+    type Target = T;
+    type Marker = M;
+
+    unsafe fn deref_chain(this: *mut Self, outer: M) -> (*mut T, M) {
+        // `Box<T>` has the same layout as `*mut T` for sized `T`
+        (ptr::read(this as *const *mut T), outer)
+    }
+}
+
 // #[doc(hidden)]
 /// Marker type for the projections used in this crate.
 /// You can use that if you need to reuse existing projections.
@@ -235,6 +280,7 @@ impl<T> Marker<T> {
         Self(PhantomData)
     }
 
+    #[deprecated(note = "`project!` no longer requires markers to have a `check` method")]
     pub fn check(&self) {}
 }
 impl<T> Clone for Marker<T> {
@@ -244,11 +290,13 @@ impl<T> Clone for Marker<T> {
 }
 
 #[doc(hidden)]
+#[deprecated(note = "`project!` works on generic types and no longer uses this check")]
 pub trait AmbiguityCheck {
     fn check(&self) -> usize {
         unreachable!()
     }
 }
+#[allow(deprecated)]
 impl<T: ?Sized> AmbiguityCheck for T {}
 
 // #[doc(hidden)]
@@ -276,17 +324,6 @@ unsafe impl<T: Projectable> DerefProjectable for MaybeDerefProjectable<T> {
         self.0.get_raw()
     }
 }
-unsafe impl<'a, T, Target, Marker> DerefProjectable for &'a MaybeDerefProjectable<T>
-where
-    &'a T: Projectable<Target = Target, Marker = Marker>,
-{
-    type Target = Target;
-    type Marker = Marker;
-
-    fn deref_raw(&self) -> (*mut Self::Target, Self::Marker) {
-        (&*self.0).get_raw()
-    }
-}
 unsafe impl<'a, 'b, T, Target, Marker> DerefProjectable for &'a &'b MaybeDerefProjectable<T>
 where
     &'a &'b T: Projectable<Target = Target, Marker = Marker>,
@@ -298,17 +335,6 @@ where
         unsafe { &*(self as *const _ as *const &&T) }.get_raw()
     }
 }
-unsafe impl<'a, 'b, 'c, T, Target, Marker> DerefProjectable for &'a &'b &'c MaybeDerefProjectable<T>
-where
-    &'a &'b &'c T: Projectable<Target = Target, Marker = Marker>,
-{
-    type Target = Target;
-    type Marker = Marker;
-
-    fn deref_raw(&self) -> (*mut Self::Target, Self::Marker) {
-        unsafe { &*(self as *const _ as *const &&&T) }.get_raw()
-    }
-}
 
 unsafe impl<T: DerefProjectable> DerefProjectable for &&&&MaybeDerefProjectable<T> {
     type Target = T::Target;
@@ -337,7 +363,7 @@ unsafe impl<T> Projectable for Owned<T> {
     type Marker = Marker<()>;
 
     fn get_raw(&self) -> (*mut Self::Target, Self::Marker) {
-        (self as *const Self as *mut Self as *mut T, Marker::new())
+        (self.0.get() as *mut T, Marker::new())
     }
 }
 impl<T> ProjectableMarker<T> for Marker<()> {
@@ -415,8 +441,9 @@ unsafe impl<T: DerefOwned> DerefProjectable for Owned<T> {
     type Marker = OwnedDropMarker<T>;
 
     fn deref_raw(&self) -> (*mut Self::Target, Self::Marker) {
-        let ptr = unsafe { &**self.0 } as *const _ as _;
-        (ptr, OwnedDropMarker(&self.0))
+        let owned = self.0.get();
+        let ptr = unsafe { (**owned).deref_mut() } as *mut _;
+        (ptr, OwnedDropMarker(owned))
     }
 }
 
@@ -429,7 +456,7 @@ impl<X: DerefOwned, T> ProjectableMarker<T> for OwnedDropMarker<X> {
 }
 
 //---------------------
-unsafe impl<'a, T> Projectable for Helper<&'a mut T> {
+unsafe impl<'a, T: ?Sized> Projectable for Helper<&'a mut T> {
     type Target = T;
     type Marker = Marker<&'a mut ()>;
 
@@ -440,7 +467,7 @@ unsafe impl<'a, T> Projectable for Helper<&'a mut T> {
         )
     }
 }
-impl<'a, T: 'a> ProjectableMarker<T> for Marker<&'a mut ()> {
+impl<'a, T: ?Sized + 'a> ProjectableMarker<T> for Marker<&'a mut ()> {
     type Output = &'a mut T;
 
     unsafe fn from_raw(&self, raw: *mut T) -> Self::Output {
@@ -484,7 +511,7 @@ unsafe impl<'a, T: DerefMut> DerefProjectable for Helper<&'a mut Pin<T>> {
 // }
 
 //---------------------
-unsafe impl<'a, T> Projectable for Helper<&'a T> {
+unsafe impl<'a, T: ?Sized> Projectable for Helper<&'a T> {
     type Target = T;
     type Marker = Marker<&'a ()>;
 
@@ -492,7 +519,7 @@ unsafe impl<'a, T> Projectable for Helper<&'a T> {
         (self.0 as *const _ as _, Marker::new())
     }
 }
-impl<'a, T: 'a> ProjectableMarker<T> for Marker<&'a ()> {
+impl<'a, T: ?Sized + 'a> ProjectableMarker<T> for Marker<&'a ()> {
     type Output = &'a T;
 
     unsafe fn from_raw(&self, raw: *mut T) -> Self::Output {
@@ -526,40 +553,34 @@ unsafe impl<'a, T: Deref> DerefProjectable for &Helper<&'a Pin<T>> {
 //     }
 // }
 //---------------------
-unsafe impl<'a, T> Projectable for &Helper<&'a Cell<T>> {
-    type Target = T;
-    type Marker = Marker<&'a Cell<()>>;
-
-    fn get_raw(&self) -> (*mut Self::Target, Self::Marker) {
-        (unsafe { transmute_copy(*self) }, Marker::new())
-    }
-}
-impl<'a, T: 'a> ProjectableMarker<T> for Marker<&'a Cell<()>> {
+impl<'a, T: ?Sized + 'a> ProjectableMarker<T> for Marker<&'a Cell<()>> {
     type Output = &'a Cell<T>;
 
     unsafe fn from_raw(&self, raw: *mut T) -> Self::Output {
         &*(raw as *mut Cell<T>)
     }
 }
+unsafe impl<'a> WrapMarker<Cell<()>> for Marker<&'a ()> {
+    type Output = Marker<&'a Cell<()>>;
 
-unsafe impl<'a, T> Projectable for &Helper<&'a mut Cell<T>> {
-    type Target = T;
-    type Marker = Marker<&'a mut ()>;
+    fn wrap_marker(self) -> Self::Output {
+        Marker::new()
+    }
+}
+// `&mut Cell<T>` is the same as `&mut T`
+unsafe impl WrapMarker<Cell<()>> for Marker<&mut ()> {
+    type Output = Self;
 
-    fn get_raw(&self) -> (*mut Self::Target, Self::Marker) {
-        (unsafe { transmute_copy(*self) }, Marker::new())
+    fn wrap_marker(self) -> Self::Output {
+        self
     }
 }
 //---------------------
-unsafe impl<'a, T> Projectable for &Helper<&'a mut MaybeUninit<T>> {
-    type Target = T;
-    type Marker = Marker<&'a mut MaybeUninit<()>>;
+unsafe impl<'a> WrapMarker<MaybeUninit<()>> for Marker<&'a mut ()> {
+    type Output = Marker<&'a mut MaybeUninit<()>>;
 
-    fn get_raw(&self) -> (*mut Self::Target, Self::Marker) {
-        (
-            unsafe { ptr::read(*self as *const _ as *const *mut Self::Target) },
-            Marker::new(),
-        )
+    fn wrap_marker(self) -> Self::Output {
+        Marker::new()
     }
 }
 impl<'a, T: 'a> ProjectableMarker<T> for Marker<&'a mut MaybeUninit<()>> {
@@ -569,11 +590,18 @@ impl<'a, T: 'a> ProjectableMarker<T> for Marker<&'a mut MaybeUninit<()>> {
         &mut *(raw as *mut MaybeUninit<T>)
     }
 }
+impl<'a, T: 'a> ProjectableMarker<[T]> for Marker<&'a mut MaybeUninit<()>> {
+    type Output = &'a mut [MaybeUninit<T>];
+
+    unsafe fn from_raw(&self, raw: *mut [T]) -> Self::Output {
+        &mut *(raw as *mut [MaybeUninit<T>])
+    }
+}
 //---------------------
-unsafe impl<T> CustomWrapper for *mut T {
+unsafe impl<T: ?Sized> CustomWrapper for *mut T {
     type Output = Self;
 }
-unsafe impl<T> Projectable for *mut T {
+unsafe impl<T: ?Sized> Projectable for *mut T {
     type Target = T;
     type Marker = Marker<*mut ()>;
 
@@ -581,7 +609,7 @@ unsafe impl<T> Projectable for *mut T {
         (*self, Marker::new())
     }
 }
-impl<T> ProjectableMarker<T> for Marker<*mut ()> {
+impl<T: ?Sized> ProjectableMarker<T> for Marker<*mut ()> {
     type Output = *mut T;
 
     unsafe fn from_raw(&self, raw: *mut T) -> Self::Output {
@@ -590,10 +618,10 @@ impl<T> ProjectableMarker<T> for Marker<*mut ()> {
 }
 
 //---------------------
-unsafe impl<T> CustomWrapper for *const T {
+unsafe impl<T: ?Sized> CustomWrapper for *const T {
     type Output = Self;
 }
-unsafe impl<T> Projectable for *const T {
+unsafe impl<T: ?Sized> Projectable for *const T {
     type Target = T;
     type Marker = Marker<*const ()>;
 
@@ -601,7 +629,7 @@ unsafe impl<T> Projectable for *const T {
         (*self as _, Marker::new())
     }
 }
-impl<T> ProjectableMarker<T> for Marker<*const ()> {
+impl<T: ?Sized> ProjectableMarker<T> for Marker<*const ()> {
     type Output = *const T;
 
     unsafe fn from_raw(&self, raw: *mut T) -> Self::Output {
@@ -610,10 +638,10 @@ impl<T> ProjectableMarker<T> for Marker<*const ()> {
 }
 
 //---------------------
-unsafe impl<T> CustomWrapper for NonNull<T> {
+unsafe impl<T: ?Sized> CustomWrapper for NonNull<T> {
     type Output = Self;
 }
-unsafe impl<T> Projectable for NonNull<T> {
+unsafe impl<T: ?Sized> Projectable for NonNull<T> {
     type Target = T;
     type Marker = Marker<NonNull<()>>;
 
@@ -621,7 +649,7 @@ unsafe impl<T> Projectable for NonNull<T> {
         (self.as_ptr(), Marker::new())
     }
 }
-impl<T> ProjectableMarker<T> for Marker<NonNull<()>> {
+impl<T: ?Sized> ProjectableMarker<T> for Marker<NonNull<()>> {
     type Output = NonNull<T>;
 
     unsafe fn from_raw(&self, raw: *mut T) -> Self::Output {
@@ -688,24 +716,449 @@ where
 }
 //----------------
 
+/// Method version of `project!(x -> field)`, for places where a method call is more convenient than macro syntax.
+///
+/// Field is selected with a [`Lens`], created by [`lens!`] or [`FieldOf::lens`], so the field pointer
+/// is computed and checked when the lens is created and projection itself is safe.
+/// Works with any pointer that [`Lens::project`] can project, including `&Cell<T>`, `&mut MaybeUninit<T>`
+/// and other transparent wrappers that `project!` looks through.
+///
+/// Result is a [`Projected`], and [`Projected::finish`] post-processes it like `project!` does,
+/// e.g. `Pin<&mut T>` is projected to `&mut Field` if `Field` is `Unpin`.
+/// ```rust
+/// # use projecture::{lens, pin_projectable, ProjectWith};
+/// # use std::cell::{Cell, RefCell};
+/// # use std::marker::PhantomPinned;
+/// # use std::pin::Pin;
+/// struct Foo {
+///     x: usize,
+///     y: (usize, usize),
+///     _p: PhantomPinned,
+/// }
+/// pin_projectable! { Foo }
+/// let mut foo = Box::pin(Foo { x: 1, y: (2, 3), _p: PhantomPinned });
+/// let y: &mut usize = foo.as_mut().project_with(lens!(Foo => y.1)).finish();
+/// *y += 1;
+/// let p: Pin<&mut PhantomPinned> = foo.as_mut().project_with(lens!(Foo => _p)).finish();
+/// # let _ = p;
+/// assert_eq!(foo.y.1, 4);
+///
+/// let cell = Cell::new((1, 2));
+/// let second: &Cell<i32> = (&cell).project_with(lens!((i32, i32) => 1)).finish();
+/// second.set(3);
+/// assert_eq!(cell.get(), (1, 3));
+///
+/// fn first<'a, T>(pair: &'a RefCell<(T, T)>) -> std::cell::RefMut<'a, T> {
+///     pair.borrow_mut().project_with(lens!((T, T) => 0)).finish()
+/// }
+/// let pair = RefCell::new((1, 2));
+/// *first(&pair) += 10;
+/// assert_eq!(*pair.borrow(), (11, 2));
+/// ```
+pub trait ProjectWith: CustomWrapper + Sized {
+    /// Projects to the field selected by `field`
+    #[allow(clippy::type_complexity)]
+    fn project_with<Outer, Field, Kind>(
+        self,
+        field: Lens<Outer, Field>,
+    ) -> Projected<
+        <<Self::Output as LensTarget<Outer, Kind>>::Marker as ProjectableMarker<Field>>::Output,
+    >
+    where
+        Self::Output: LensTarget<Outer, Kind>,
+        <Self::Output as LensTarget<Outer, Kind>>::Marker: ProjectableMarker<Field>,
+    {
+        Projected {
+            value: ManuallyDrop::new(field.project(self)),
+            refs: (Cell::new(ptr::null()), Cell::new(ptr::null())),
+            finished: Cell::new(false),
+        }
+    }
+}
+impl<T: CustomWrapper> ProjectWith for T {}
+
+/// Result of [`ProjectWith::project_with`], which is turned into the projected pointer by [`finish`](Projected::finish).
+///
+/// `finish` is resolved for the concrete type where it is called, so it applies [`FinalizeProjection`]
+/// just like `project!` does. In generic code it returns the pointer that the marker gave as is.
+/// If `finish` is never called, the pointer is dropped with `Projected`.
+pub struct Projected<X> {
+    value: ManuallyDrop<X>,
+    // references to `value` and to `refs.0` are written here by `finish`,
+    // so that `FinalizeProjection` gets a tower of references that live as long as `self` is borrowed
+    refs: (Cell<*const X>, Cell<*const *const X>),
+    finished: Cell<bool>,
+}
+impl<X> Drop for Projected<X> {
+    fn drop(&mut self) {
+        if !self.finished.get() {
+            unsafe { ManuallyDrop::drop(&mut self.value) }
+        }
+    }
+}
+impl<X> Projected<X> {
+    /// Marks the value as moved out and gives `&'a &'a &'a X`, so the result of `FinalizeProjection`
+    /// can borrow only from `self`, like the result of `project!` can borrow only from the projected value.
+    fn take_refs(&self) -> &&&X {
+        assert!(
+            !self.finished.get(),
+            "`Projected::finish` can be called only once"
+        );
+        self.finished.set(true);
+        self.refs.0.set(&*self.value);
+        self.refs.1.set(self.refs.0.as_ptr());
+        unsafe { &*(self.refs.1.as_ptr() as *const &&X) }
+    }
+}
+
+// `finish` is found on `&Projected` first, then on each next `FinishLevel` through `Deref`,
+// like `Finalizer` is found on each level of `&&&&&ManuallyDrop`
+#[doc(hidden)]
+#[repr(transparent)]
+pub struct FinishLevel<X, const LEVEL: usize>(Projected<X>);
+impl<X> Deref for Projected<X> {
+    type Target = FinishLevel<X, 1>;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*(self as *const Self as *const Self::Target) }
+    }
+}
+macro_rules! impl_finish_level_deref {
+    ($($level:literal => $next:literal),*) => {$(
+        impl<X> Deref for FinishLevel<X, $level> {
+            type Target = FinishLevel<X, $next>;
+
+            fn deref(&self) -> &Self::Target {
+                unsafe { &*(self as *const Self as *const Self::Target) }
+            }
+        }
+    )*};
+}
+impl_finish_level_deref! { 1 => 2, 2 => 3, 3 => 4 }
+
+// Impls have different where clauses, so the first one that applies is used.
+impl<'a, X: 'a> Projected<X>
+where
+    &'a &'a &'a X: FinalizeProjection,
+{
+    /// Gives the projected pointer, post-processed like `project!` does.
+    ///
+    /// Panics if it was already called on the same `Projected`.
+    pub fn finish(&'a self) -> <&'a &'a &'a X as FinalizeProjection>::Output {
+        unsafe { <&&&X as FinalizeProjection>::finalize(&self.take_refs()) }
+    }
+}
+impl<'a, X: 'a> FinishLevel<X, 1>
+where
+    &'a &'a X: FinalizeProjection,
+{
+    pub fn finish(&'a self) -> <&'a &'a X as FinalizeProjection>::Output {
+        unsafe { <&&X as FinalizeProjection>::finalize(self.0.take_refs()) }
+    }
+}
+impl<'a, X: 'a> FinishLevel<X, 2>
+where
+    &'a X: FinalizeProjection,
+{
+    pub fn finish(&'a self) -> <&'a X as FinalizeProjection>::Output {
+        unsafe { <&X as FinalizeProjection>::finalize(*self.0.take_refs()) }
+    }
+}
+impl<X: FinalizeProjection> FinishLevel<X, 3> {
+    pub fn finish(&self) -> X::Output {
+        unsafe { X::finalize(**self.0.take_refs()) }
+    }
+}
+impl<X> FinishLevel<X, 4> {
+    pub fn finish(&self) -> X {
+        unsafe { transmute_copy(**self.0.take_refs()) }
+    }
+}
+
 #[doc(hidden)]
 pub trait CheckNoDeref {
     type Result;
     fn check_deref(&self) -> Self::Result;
 }
 
-impl<T> CheckNoDeref for *mut T {
+impl<T: ?Sized> CheckNoDeref for *mut T {
     type Result = *mut T;
     fn check_deref(&self) -> Self::Result {
         *self
     }
 }
 
-impl<T: Deref> CheckNoDeref for &*mut T {
-    type Result = *mut Infallible;
+impl<T: ?Sized + Deref> CheckNoDeref for &*mut T {
+    type Result = *mut UseExplicitDerefPattern<T>;
+
+    fn check_deref(&self) -> Self::Result {
+        **self as _
+    }
+}
+
+/// Type that shows up in compile errors when `project!(x -> field)` is used on a type that implements `Deref`.
+///
+/// `(*ptr).field` would silently go through `Deref::deref` for such types, so `project!` replaces the pointer
+/// with a pointer to this type, which has no fields and can't be indexed, and compilation fails with
+/// ``no field `field` on type `UseExplicitDerefPattern<T>` ``. Use an explicit deref pattern instead.
+/// ```rust,compile_fail
+/// # use std::rc::Rc;
+/// # use projecture::project;
+/// struct Foo {
+///     rc: Rc<(usize, usize)>,
+/// }
+/// let mut foo = Foo { rc: Rc::new((1, 2)) };
+/// let foo_ref = &mut foo;
+/// let x = project!(foo_ref -> rc -> 0);
+/// ```
+/// ```rust
+/// # use std::rc::Rc;
+/// # use projecture::project;
+/// # struct Foo {
+/// #     rc: Rc<(usize, usize)>,
+/// # }
+/// # let foo = Foo { rc: Rc::new((1, 2)) };
+/// project!(let Foo { rc: *(x, _) } = &foo);
+/// assert_eq!(*x, 1);
+/// ```
+pub enum UseExplicitDerefPattern<T: ?Sized> {
+    #[doc(hidden)]
+    __Never(Infallible, PhantomData<T>),
+}
+
+//----------------
+
+/// Implemented by markers that can project through a pointer to `W<T>`, where `W` is a transparent wrapper
+/// like `Cell` that is projected as a part of the pointer (`&Cell<Struct>` gives `&Cell<Field>`).
+/// `W` is the wrapper with `()` inside, and `Output` is a marker of the projection through `W`.
+///
+/// # Safety
+/// `W` must be a transparent wrapper, and `Output` must not give more access to a field of `T`
+/// than `Self` gives to the same field inside of `W<T>`.
+pub unsafe trait WrapMarker<W> {
+    type Output;
+    fn wrap_marker(self) -> Self::Output;
+}
+
+// looks through `Cell`, `MaybeUninit` and other transparent wrappers in the projection target,
+// this is done on the raw pointer so it works the same way however deeply the pointer itself is nested.
+// Must be called at most once on the result of `get_raw`, and the projected value must be forgotten afterwards
+#[doc(hidden)]
+pub trait UnwrapTarget {
+    type Target: ?Sized;
+    type Marker;
+    unsafe fn unwrap_target(&self) -> (*mut Self::Target, Self::Marker);
+}
+impl<T: ?Sized, M> UnwrapTarget for ManuallyDrop<(*mut T, M)> {
+    type Target = T;
+    type Marker = M;
+
+    unsafe fn unwrap_target(&self) -> (*mut Self::Target, Self::Marker) {
+        transmute_copy(self)
+    }
+}
+impl<T: ?Sized, M: WrapMarker<Cell<()>>> UnwrapTarget for &ManuallyDrop<(*mut Cell<T>, M)> {
+    type Target = T;
+    type Marker = M::Output;
+
+    unsafe fn unwrap_target(&self) -> (*mut Self::Target, Self::Marker) {
+        let (ptr, marker): (*mut Cell<T>, M) = transmute_copy(*self);
+        (transmute_copy(&ptr), marker.wrap_marker())
+    }
+}
+impl<T, M: WrapMarker<MaybeUninit<()>>> UnwrapTarget for &ManuallyDrop<(*mut MaybeUninit<T>, M)> {
+    type Target = T;
+    type Marker = M::Output;
+
+    unsafe fn unwrap_target(&self) -> (*mut Self::Target, Self::Marker) {
+        let (ptr, marker): (*mut MaybeUninit<T>, M) = transmute_copy(*self);
+        (ptr as *mut T, marker.wrap_marker())
+    }
+}
+
+//----------------
+
+/// Arrays and slices, i.e. types that support index projection like `project!(x -> [i])`
+#[doc(hidden)]
+pub unsafe trait ArrayLike {
+    type Item;
+    fn as_item_ptr(ptr: *mut Self) -> *mut Self::Item;
+    fn len(ptr: *mut Self) -> usize;
+
+    fn item_ptr(ptr: *mut Self, index: usize, from_end: bool) -> *mut Self::Item {
+        let index = if from_end {
+            Self::len(ptr) - index
+        } else {
+            index
+        };
+        Self::as_item_ptr(ptr).wrapping_add(index)
+    }
+
+    fn rest_ptr(ptr: *mut Self, before: usize, after: usize) -> *mut [Self::Item] {
+        let len = Self::len(ptr) - before - after;
+        ptr::slice_from_raw_parts_mut(Self::as_item_ptr(ptr).wrapping_add(before), len)
+    }
+}
+
+unsafe impl<T, const N: usize> ArrayLike for [T; N] {
+    type Item = T;
+    fn as_item_ptr(ptr: *mut Self) -> *mut T {
+        ptr as *mut T
+    }
+    fn len(_ptr: *mut Self) -> usize {
+        N
+    }
+}
+
+unsafe impl<T> ArrayLike for [T] {
+    type Item = T;
+    fn as_item_ptr(ptr: *mut Self) -> *mut T {
+        ptr as *mut T
+    }
+    fn len(ptr: *mut Self) -> usize {
+        assert!(
+            !ptr.is_null(),
+            "can't get the length of a null slice pointer"
+        );
+        // reference to a zero sized slice is valid for any non-null pointer
+        unsafe { (&*(ptr as *const [()])).len() }
+    }
+}
+
+/// Types that can be used as an index in `project!(x -> [index])`, i.e. `usize` and ranges of `usize`.
+///
+/// # Safety
+/// If `checked` is true, returned pointer must be within the `len` items starting at `ptr`.
+pub unsafe trait ProjectionIndex<T> {
+    /// Either `T` or `[T]`
+    type Output: ?Sized;
+
+    /// Offsets pointer to the first item of an array or slice, `len` is called only if the length is actually needed.
+    /// If `checked` is true, panics when index is out of bounds, otherwise does wrapping pointer arithmetic.
+    fn index_raw(
+        self,
+        ptr: *mut T,
+        len: impl FnOnce() -> usize,
+        checked: bool,
+    ) -> *mut Self::Output;
+}
+
+unsafe impl<T> ProjectionIndex<T> for usize {
+    type Output = T;
+
+    fn index_raw(self, ptr: *mut T, len: impl FnOnce() -> usize, checked: bool) -> *mut T {
+        if checked {
+            let len = len();
+            if self >= len {
+                panic!(
+                    "index out of bounds: the len is {} but the index is {}",
+                    len, self
+                )
+            }
+        }
+        ptr.wrapping_add(self)
+    }
+}
+
+fn index_range<T>(
+    ptr: *mut T,
+    start: usize,
+    end: Option<usize>,
+    len: impl FnOnce() -> usize,
+    checked: bool,
+) -> *mut [T] {
+    let end = match end {
+        Some(end) if !checked => end,
+        end => {
+            let len = len();
+            let end = end.unwrap_or(len);
+            if checked && start > end {
+                panic!("slice index starts at {} but ends at {}", start, end)
+            }
+            if checked && end > len {
+                panic!(
+                    "range end index {} out of range for slice of length {}",
+                    end, len
+                )
+            }
+            end
+        }
+    };
+    ptr::slice_from_raw_parts_mut(ptr.wrapping_add(start), end.wrapping_sub(start))
+}
+
+macro_rules! impl_range_index {
+    ($($range:ty => |$this:ident| ($start:expr, $end:expr)),*) => {$(
+        unsafe impl<T> ProjectionIndex<T> for $range {
+            type Output = [T];
+
+            fn index_raw(self, ptr: *mut T, len: impl FnOnce() -> usize, checked: bool) -> *mut [T] {
+                let $this = self;
+                index_range(ptr, $start, $end, len, checked)
+            }
+        }
+    )*};
+}
+
+impl_range_index! {
+    Range<usize> => |this| (this.start, Some(this.end)),
+    RangeFrom<usize> => |this| (this.start, None),
+    RangeTo<usize> => |this| (0, Some(this.end)),
+    RangeFull => |_this| (0, None),
+    RangeInclusive<usize> => |this| (*this.start(), Some(inclusive_end(*this.end()))),
+    RangeToInclusive<usize> => |this| (0, Some(inclusive_end(this.end)))
+}
+
+fn inclusive_end(end: usize) -> usize {
+    end.checked_add(1)
+        .expect("attempted to index slice up to maximum usize")
+}
+
+/// Implement this on your marker if projection should do wrapping pointer arithmetic
+/// like [`pointer::wrapping_add`](https://doc.rust-lang.org/std/primitive.pointer.html#method.wrapping_add),
+/// so that index out of bounds doesn't panic, and fields of dangling pointers can be projected.
+/// Pointers of other markers are projected in place with `addr_of_mut!`,
+/// so they must be null or point to a live value.
+///
+/// # Safety
+/// Projected pointer must be allowed to dangle, so it must not be implemented for markers that create references.
+pub unsafe trait WrappingIndex {}
+
+unsafe impl WrappingIndex for Marker<*mut ()> {}
+unsafe impl WrappingIndex for Marker<*const ()> {}
+unsafe impl WrappingIndex for Marker<NonNull<()>> {}
+
+#[doc(hidden)]
+pub trait IndexProjection<I> {
+    type Output: ?Sized;
+    fn index_raw(&self, index: I) -> *mut Self::Output;
+}
+
+impl<A, M, I> IndexProjection<I> for (*mut A, M)
+where
+    A: ?Sized + ArrayLike,
+    I: ProjectionIndex<A::Item>,
+{
+    type Output = I::Output;
+
+    fn index_raw(&self, index: I) -> *mut I::Output {
+        let ptr = self.0;
+        // null here means that there is nothing to project, e.g. `None` in `Option` projection
+        index.index_raw(A::as_item_ptr(ptr), || A::len(ptr), !ptr.is_null())
+    }
+}
+
+impl<A, M, I> IndexProjection<I> for &(*mut A, &M)
+where
+    A: ?Sized + ArrayLike,
+    M: WrappingIndex,
+    I: ProjectionIndex<A::Item>,
+{
+    type Output = I::Output;
 
-    fn check_deref(&self) -> *mut Infallible {
-        panic!("can't go through deref here, use more explicit syntax")
+    fn index_raw(&self, index: I) -> *mut I::Output {
+        let ptr = self.0;
+        index.index_raw(A::as_item_ptr(ptr), || A::len(ptr), false)
     }
 }
 
@@ -739,26 +1192,409 @@ unsafe impl<ToCheck, NotPacked> SupportsPacked
     type Result = NotPacked;
 }
 
-/// Macro to do all kinds of projections
-///
-/// Has two modes:
-///  - `let` syntax very similar to regular rust's `let <pattern> = <expr>`.
-///    Basically it is exactly the same but also has additional support for deref patterns and does not yet supports bindings via `@`.
-///  - single field projection `project!(<variable> -> <field>)` or `project!((<expression>) -> <field>)`.
-///     Basically same as doing `let` option with one field, but this one is an expression while `let` one is a statement.
-///     Also this variant additionally tries to do an implicit deref projection if possible.
-///     Note though that you will get an error if inner type of projection implements `Deref`.
-///     This is caused by the fact that Rust's `.` operator(which is used by this macro) can go through an implicit deref call
-///     which would ruin all unsafe logic.
+//----------------
+
+/// Implement this on your marker only if your projection can work with enums.
 ///
-/// ```rust
-/// #   use std::cell::Cell;
-/// #   use std::mem::MaybeUninit;
-/// #   use projecture::project;
-///     struct Foo {
-///         x: usize,
-///         y: usize,
-///     }
+/// # Safety
+/// Pointer must always point to a valid initialized value
+/// and nobody else must be able to change its variant while projected fields are alive.
+/// So it must not be implemented for things like `&Cell<T>` or `&mut MaybeUninit<T>`.
+pub unsafe trait SupportsEnums {}
+
+unsafe impl SupportsEnums for Marker<()> {}
+unsafe impl SupportsEnums for Marker<&()> {}
+unsafe impl SupportsEnums for Marker<&mut ()> {}
+unsafe impl<T: DerefOwned> SupportsEnums for OwnedDropMarker<T> {}
+unsafe impl<'a, T> SupportsEnums for OwningMarker<'a, T> {}
+
+/// Implemented for projection results that can be duplicated,
+/// so they can be used with `@` bindings like `project!(let foo @ Foo { x, .. } = &foo)`.
+pub trait Duplicate {
+    fn duplicate(&self) -> Self;
+}
+impl<T: ?Sized> Duplicate for &T {
+    fn duplicate(&self) -> Self {
+        self
+    }
+}
+impl<T: ?Sized> Duplicate for Pin<&T> {
+    fn duplicate(&self) -> Self {
+        *self
+    }
+}
+impl<T: ?Sized> Duplicate for *const T {
+    fn duplicate(&self) -> Self {
+        *self
+    }
+}
+impl<T: ?Sized> Duplicate for *mut T {
+    fn duplicate(&self) -> Self {
+        *self
+    }
+}
+impl<T: ?Sized> Duplicate for NonNull<T> {
+    fn duplicate(&self) -> Self {
+        *self
+    }
+}
+impl<T: Duplicate> Duplicate for Option<T> {
+    fn duplicate(&self) -> Self {
+        self.as_ref().map(Duplicate::duplicate)
+    }
+}
+
+#[doc(hidden)]
+pub trait CheckEnum {
+    fn check_enum(&self) {}
+}
+impl<M: SupportsEnums> CheckEnum for M {}
+
+/// Raw pointers are not guaranteed to point to a valid value,
+/// so reading the variant through them requires `unsafe`.
+#[doc(hidden)]
+pub trait CheckEnumRaw {
+    /// Safety requirements: pointer must be valid for reads
+    unsafe fn check_enum(&self) {}
+}
+impl CheckEnumRaw for Marker<*mut ()> {}
+impl CheckEnumRaw for Marker<*const ()> {}
+impl CheckEnumRaw for Marker<NonNull<()>> {}
+
+//----------------
+
+/// Lists all fields of a type so that owned destructuring with `..` can drop the fields
+/// that were not mentioned in the pattern instead of leaking them.
+///
+/// Should be implemented via [`Fields`] derive.
+///
+/// # Safety
+/// `drop_fields_except` must drop exactly the fields whose names are not in `M::MOVED`
+/// (for tuple fields the name is its index, like `"0"`, or its index from the end, like `"-1"`)
+/// and must not call `Drop` of `Self`.
+pub unsafe trait DropFields {
+    /// Drops in place all fields(of the current variant for enums) except the ones listed in `M::MOVED`.
+    ///
+    /// # Safety
+    /// `this` must point to a valid value which is not used afterwards except for the fields listed in `M::MOVED`.
+    unsafe fn drop_fields_except<M: MovedFields>(this: *mut Self);
+}
+
+/// Fields that were moved out by the pattern, implemented by `project!` for a local type,
+/// so that [`DropFields`] can find out at compile time which fields it has to drop.
+pub trait MovedFields {
+    const MOVED: &'static [&'static str];
+}
+impl MovedFields for () {
+    const MOVED: &'static [&'static str] = &[];
+}
+
+// Enum that is matched by `project!`, it is accessed through the projected pointer itself,
+// so interior mutable fields are not read and the value is not copied.
+// Only raw pointers into `#[repr(packed)]` structs can be unaligned, then it is a bitwise copy.
+#[doc(hidden)]
+pub struct EnumPlace<T>(*const T, MaybeUninit<T>);
+impl<T> EnumPlace<T> {
+    /// # Safety
+    /// `ptr` must point to a valid value, which is not mutated while the place is alive.
+    pub unsafe fn new(ptr: *mut T) -> Self {
+        if ptr as usize & (core::mem::align_of::<T>() - 1) == 0 {
+            EnumPlace(ptr, MaybeUninit::uninit())
+        } else {
+            let copy = MaybeUninit::new(ptr::read_unaligned(ptr));
+            EnumPlace(ptr::null(), copy)
+        }
+    }
+}
+impl<T> Deref for EnumPlace<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        if self.0.is_null() {
+            unsafe { &*self.1.as_ptr() }
+        } else {
+            unsafe { &*self.0 }
+        }
+    }
+}
+
+/// Pointer to the field of the enum behind `ptr`, the `field` reference is taken from the `place` that was matched.
+#[doc(hidden)]
+pub fn variant_field_ptr<T, U>(ptr: *mut T, place: &T, field: &U) -> *mut U {
+    let offset = field as *const U as usize - place as *const T as usize;
+    (ptr as *mut u8).wrapping_add(offset) as *mut U
+}
+
+/// Drops value behind a field pointer which might be unaligned if the struct is `#[repr(packed)]`.
+#[doc(hidden)]
+pub unsafe fn drop_field<T>(ptr: *mut T) {
+    if ptr as usize & (core::mem::align_of::<T>() - 1) == 0 {
+        drop_in_place(ptr)
+    } else {
+        drop(ptr::read_unaligned(ptr))
+    }
+}
+
+/// Drops the field with [`drop_field`] when it goes out of scope, or does nothing if the pointer is null.
+/// A tuple of them drops the rest of the fields even if one of the drops panics, like the drop glue of a struct.
+#[doc(hidden)]
+pub struct DropFieldGuard<T>(*mut T);
+impl<T> DropFieldGuard<T> {
+    /// # Safety
+    /// `ptr` must be null or point to a valid field that is not used after the guard is dropped.
+    pub unsafe fn new(ptr: *mut T) -> Self {
+        DropFieldGuard(ptr)
+    }
+}
+impl<T> Drop for DropFieldGuard<T> {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe { drop_field(self.0) }
+        }
+    }
+}
+
+/// Chooses how `project!` finds the field pointer: pointers of markers that implement [`WrappingIndex`]
+/// may dangle, so their fields are found with [`uninit_field`], other pointers are projected in place.
+#[doc(hidden)]
+pub trait MayDangle {
+    fn may_dangle(&self) -> bool {
+        false
+    }
+}
+impl<M> MayDangle for (&M,) {}
+impl<M: WrappingIndex> MayDangle for &(&M,) {
+    fn may_dangle(&self) -> bool {
+        true
+    }
+}
+
+// field offset is taken from a `MaybeUninit` in the frame of this function, so the projected memory itself
+// is accessed only through `ptr`, and pointer arithmetic wraps like for `WrappingIndex`.
+// It is not `#[inline(always)]`, so that in debug builds the `MaybeUninit` is not reserved in the caller's frame.
+#[doc(hidden)]
+#[inline]
+pub fn uninit_field<T, U>(ptr: *mut T, field: fn(*mut T) -> *mut U) -> *mut U {
+    let mut uninit = MaybeUninit::<T>::uninit();
+    let base = uninit.as_mut_ptr();
+    let offset = (field(base) as usize).wrapping_sub(base as usize);
+    (ptr as *mut u8).wrapping_add(offset) as *mut U
+}
+
+// Same as `uninit_field`, but for all fields of a struct pattern at once, so that there is one closure
+// per pattern instead of one per field. Offsets are only computed if the pointer may dangle.
+#[doc(hidden)]
+#[inline]
+pub fn uninit_offsets<T, const N: usize>(
+    _ptr: *mut T,
+    may_dangle: bool,
+    fields: fn(*mut T) -> [*mut u8; N],
+) -> [usize; N] {
+    let mut offsets = [0; N];
+    if may_dangle {
+        let mut uninit = MaybeUninit::<T>::uninit();
+        let base = uninit.as_mut_ptr();
+        for (offset, field) in offsets.iter_mut().zip(fields(base).iter()) {
+            *offset = (*field as usize).wrapping_sub(base as usize);
+        }
+    }
+    offsets
+}
+
+// fields take their offsets from `uninit_offsets` in the order they are projected
+#[doc(hidden)]
+#[inline]
+pub fn next_field<T, U>(ptr: *mut T, offsets: &mut core::slice::Iter<'_, usize>) -> *mut U {
+    let offset = offsets.next().copied().unwrap_or_default();
+    (ptr as *mut u8).wrapping_add(offset) as *mut U
+}
+
+// Used to check at compile time that the same field is not projected twice,
+// so that the error says "the trait bound `SameFieldProjectedTwice<true>: FieldsDistinct` is not satisfied".
+// Const panics would give a better message but they need Rust 1.57.
+#[doc(hidden)]
+pub struct SameFieldProjectedTwice<const TWICE: bool>;
+#[doc(hidden)]
+pub trait FieldsDistinct {}
+impl FieldsDistinct for SameFieldProjectedTwice<false> {}
+#[doc(hidden)]
+pub fn assert_fields_distinct<T: FieldsDistinct>() {}
+
+// Same as above, but for bindings of destructuring assignment that look like unit variants or constants,
+// the error says "the trait bound `UppercaseBinding<true>: BindingName` is not satisfied".
+#[doc(hidden)]
+pub struct UppercaseBinding<const UPPERCASE: bool>;
+#[doc(hidden)]
+pub trait BindingName {}
+impl BindingName for UppercaseBinding<false> {}
+#[doc(hidden)]
+pub fn assert_binding_name<T: BindingName>() {}
+
+#[doc(hidden)]
+pub const fn is_binding_name(name: &str) -> bool {
+    let first = name.as_bytes()[0];
+    first == b'_' || first.is_ascii_lowercase() || !first.is_ascii()
+}
+
+#[doc(hidden)]
+pub const fn all_distinct(names: &[&str]) -> bool {
+    let mut i = 0;
+    while i < names.len() {
+        let mut j = i + 1;
+        while j < names.len() {
+            if str_eq(names[i], names[j]) {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+// used by `Fields` derive in constants, so that each field is checked at compile time
+#[doc(hidden)]
+pub const fn is_field_moved(moved: &[&str], name: &str) -> bool {
+    let mut i = 0;
+    while i < moved.len() {
+        if str_eq(moved[i], name) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+// fields after `..` in tuple patterns are named by their negative index
+#[doc(hidden)]
+pub const fn is_tuple_field_moved(moved: &[&str], index: usize, count: usize) -> bool {
+    let mut i = 0;
+    while i < moved.len() {
+        let name = moved[i].as_bytes();
+        let moved_index = match name {
+            [b'-', ..] => match parse_index(name, 1) {
+                Some(from_end) if from_end <= count => Some(count - from_end),
+                _ => None,
+            },
+            _ => parse_index(name, 0),
+        };
+        if let Some(moved_index) = moved_index {
+            if moved_index == index {
+                return true;
+            }
+        }
+        i += 1;
+    }
+    false
+}
+
+const fn parse_index(digits: &[u8], start: usize) -> Option<usize> {
+    if start == digits.len() {
+        return None;
+    }
+    let mut i = start;
+    let mut index = 0;
+    while i < digits.len() {
+        if !digits[i].is_ascii_digit() {
+            return None;
+        }
+        index = index * 10 + (digits[i] - b'0') as usize;
+        i += 1;
+    }
+    Some(index)
+}
+
+// used to drop the items of an array that were not mentioned in the pattern,
+// `project!` passes only the rest of the array which has no moved items
+unsafe impl<T> DropFields for [T] {
+    unsafe fn drop_fields_except<M: MovedFields>(this: *mut Self) {
+        debug_assert!(M::MOVED.is_empty());
+        // if an item panics, `rest` drops the items after it
+        struct Rest<T>(*mut [T], usize);
+        impl<T> Drop for Rest<T> {
+            fn drop(&mut self) {
+                while self.1 < <[T] as ArrayLike>::len(self.0) {
+                    self.1 += 1;
+                    unsafe { drop_field(<[T] as ArrayLike>::item_ptr(self.0, self.1 - 1, false)) }
+                }
+            }
+        }
+        let mut rest = Rest(this, 0);
+        while rest.1 < <[T] as ArrayLike>::len(this) {
+            rest.1 += 1;
+            drop_field(<[T] as ArrayLike>::item_ptr(this, rest.1 - 1, false))
+        }
+    }
+}
+
+// only owned projections have to drop the rest,
+// and without [`DropFields`] implementation the rest is leaked
+#[doc(hidden)]
+pub trait DropRemainingFields {
+    unsafe fn drop_remaining<M: MovedFields>(&self) {}
+}
+impl<T: ?Sized, M> DropRemainingFields for (*mut T, M) {}
+impl<T: ?Sized + DropFields> DropRemainingFields for &(*mut T, &Marker<()>) {
+    unsafe fn drop_remaining<M: MovedFields>(&self) {
+        T::drop_fields_except::<M>(self.0)
+    }
+}
+impl<T: ?Sized + DropFields, X: DerefOwned> DropRemainingFields for &(*mut T, &OwnedDropMarker<X>) {
+    unsafe fn drop_remaining<M: MovedFields>(&self) {
+        T::drop_fields_except::<M>(self.0)
+    }
+}
+
+/// Macro to do all kinds of projections
+///
+/// Has three modes:
+///  - `let` syntax very similar to regular rust's `let <pattern> = <expr>`.
+///    Basically it is exactly the same but also has additional support for deref patterns.
+///    Struct names can be full paths with generic arguments like `crate::foo::Foo::<u8> { .. }` or `Self(..)`.
+///    `@` bindings are supported only where the projection result can be duplicated (see [`Duplicate`]).
+///  - destructuring assignment `project!(<pattern> = <expr>)`, like regular rust's `(a, b) = (b, a)`,
+///    assigns projected fields to already declared variables instead of introducing new bindings.
+///    Useful to update cursors that live outside of a loop. Every bare identifier in the pattern is a binding,
+///    so unit variants and constants have to be written as paths like `Kind::Empty` or `Self::MAX`.
+///  - enum projection via `match`, `if let` and `let .. else` syntax, works for all projections that implement [`SupportsEnums`].
+///  - single field projection `project!(<variable> -> <field>)` or `project!((<expression>) -> <field>)`.
+///     Basically same as doing `let` option with one field, but this one is an expression while `let` one is a statement.
+///    Fields can be chained like `project!(x -> a -> b)`, and method calls can be inserted in the chain
+///    like `project!(x -> a -> as_mut() -> b)`, projection then continues on whatever the method returns.
+///    `?` after a field that is an `Option` continues projection inside of it, so `project!(x -> a ? -> b)`
+///    gives `Option<&mut B>` for `x: &mut X`. Nested options are flattened along the way.
+///     Also this variant additionally tries to do an implicit deref projection if possible.
+///     Note though that you will get a compile error if inner type of projection implements `Deref` (see [`UseExplicitDerefPattern`]).
+///     This is caused by the fact that Rust's `.` operator(which is used by this macro) can go through an implicit deref call
+///     which would ruin all unsafe logic.
+///  - several fields at once `project!(x -> {a, b, c: *inner})`, which evaluates to a tuple of projected fields.
+///    Just like with `let` each field can be mentioned only once.
+///  - index projection `project!(x -> items -> [i])` or `project!(x -> items -> [a..b])` for arrays and slices,
+///    range gives projected slice. Index out of bounds panics, except for raw pointers where it wraps (see [`WrappingIndex`]).
+///
+/// ```rust
+/// #   use std::cell::Cell;
+/// #   use std::mem::MaybeUninit;
+/// #   use projecture::project;
+///     struct Foo {
+///         x: usize,
+///         y: usize,
+///     }
 ///     let cell = Cell::new(Foo { x: 0, y: 0 });
 ///     let mut cell_ref = &cell;
 ///     // project only one field
@@ -791,9 +1627,32 @@ unsafe impl<ToCheck, NotPacked> SupportsPacked
 ///     let mut foo = Bar(1, 2);
 ///     project! { let Bar(x,y) = &mut foo }
 ///     project! { let Bar{ 0: x, 1: y } = &mut foo }
+///     let (first, second);
+///     project!(Bar(first, second) = &mut foo);
+///     core::mem::swap(first, second);
 ///     let mut foo_mut = &mut foo;
 ///     *project!(foo_mut -> 0) = 1;
 ///     *project!((&mut foo) -> 0) = 1;
+///
+///     struct Baz {
+///         bar: Option<Bar>,
+///     }
+///     let mut baz = Baz { bar: Some(Bar(1, 2)) };
+///     let baz_mut = &mut baz;
+///     let x: Option<&mut usize> = project!(baz_mut -> bar -> as_mut() -> 1);
+///     assert_eq!(x, Some(&mut 2));
+///
+///     struct Node {
+///         next: Option<Box<Node>>,
+///         value: usize,
+///     }
+///     let mut list = Node { value: 0, next: Some(Box::new(Node { value: 1, next: None })) };
+///     let list_mut = &mut list;
+///     let x: Option<&mut usize> = project!(list_mut -> next ? -> value);
+///     assert_eq!(x, Some(&mut 1));
+///     let list_mut = &mut list;
+///     let x: Option<&mut usize> = project!(list_mut -> next ? -> next ? -> value);
+///     assert_eq!(x, None);
 /// ```
 /// It supports dereferencing during pattern matching
 /// ```rust
@@ -808,6 +1667,9 @@ unsafe impl<ToCheck, NotPacked> SupportsPacked
 ///     };
 ///     project!( let Foo{ x: *x, y: *y }  = &mut foo );
 ///     let x: &mut usize = x;
+///     let (x, y) = project!((&mut foo) -> { x: *x, y });
+///     let x: &mut usize = x;
+///     let y: &mut Box<usize> = y;
 ///     project!( let Foo{ x: * x, y: * ref mut y }  = foo );
 ///     let x: usize = x;
 ///     let y: &mut usize = y;
@@ -835,6 +1697,9 @@ unsafe impl<ToCheck, NotPacked> SupportsPacked
 ///         let e: Pin<&mut PhantomPinned> = e;
 ///     }
 /// ```
+/// As shown above, projection works in generic code as well. Result type depends only on what is known about
+/// the field type where `project!` is used, so generic field `b: T` is projected as `Pin<&mut T>`,
+/// while `c: U` gets `&mut U` because of the `U: Unpin` bound, just like a concrete `Unpin` type would.
 /// `Option` projection, which also works together with other projections
 /// ```rust
 /// # use std::marker::PhantomPinned;
@@ -855,11 +1720,12 @@ unsafe impl<ToCheck, NotPacked> SupportsPacked
 /// let x: Option<Box<usize>> = x;
 /// let y: Option<usize> = y;
 ///
-/// # // todo
-/// # // fn test_pin(arg: Option<Pin<&mut Foo>>){
-/// # //     project!(let Foo { p, ..} = arg );
-/// # //     let p: Option<Pin<&mut PhantomPinned>> = p;
-/// # // }
+///
+/// fn test_pin(arg: Option<Pin<&mut Foo>>) {
+///     project!(let Foo { p, y, .. } = arg);
+///     let p: Option<Pin<&mut PhantomPinned>> = p;
+///     let y: Option<&mut usize> = y;
+/// }
 /// ```
 /// `Ref`/`RefMut` projection
 /// ```rust
@@ -893,46 +1759,199 @@ unsafe impl<ToCheck, NotPacked> SupportsPacked
 /// let val = field_ptr.map(|ptr|unsafe { ptr.as_ptr().read_unaligned() } );
 /// assert_eq!(val,Some(2));
 /// ```
+/// Index projection
+/// ```rust
+/// # use std::cell::Cell;
+/// # use std::pin::Pin;
+/// # use projecture::project;
+/// struct Foo {
+///     items: [(usize, usize); 4],
+/// }
+/// let mut foo = Foo { items: [(0, 0); 4] };
+/// *project!((&mut foo) -> items -> [1] -> 0) = 1;
+/// let tail: &mut [(usize, usize)] = project!((&mut foo) -> items -> [2..]);
+/// tail[0].1 = 2;
+/// assert_eq!(foo.items[..3], [(0, 0), (1, 0), (0, 2)]);
+///
+/// let cell = Cell::new([1, 2, 3]);
+/// let cell_ref = &cell;
+/// project!(cell_ref -> [0]).set(4);
+/// assert_eq!(cell.get(), [4, 2, 3]);
+///
+/// let mut vec = vec![1, 2, 3];
+/// let vec_mut = &mut vec;
+/// // goes through `Deref` of `Vec`
+/// let x: &mut i32 = project!(vec_mut -> [2]);
+/// *x = 4;
+///
+/// fn first<T>(pinned: Pin<&mut [T; 2]>) -> Pin<&mut T> {
+///     project!(pinned -> [0])
+/// }
+///
+/// let ptr = vec.as_ptr() as *const [i32; 3];
+/// let out_of_bounds: *const i32 = project!(ptr -> [5]);
+/// ```
+/// Enum projection.
+/// Arms can only be `_`, unit variant or variant with fields whose patterns are the same as in `let` mode
+/// but also can be refutable(literals, ranges, nested variants), guards and `|` patterns are not supported.
+/// Just like with structs enum's `Drop` is not called when variant was destructured.
+/// Enums declared with [`pin_projectable!`] are projected through `Pin` just like structs, with fields wrapped in [`Unpinned`]
+/// projected as plain references, so they can be used for hand-written future state machines.
+/// Raw pointers are not guaranteed to point to a valid enum so they can be matched only inside `unsafe`.
+/// ```rust
+/// # use std::pin::Pin;
+/// # use std::marker::PhantomPinned;
+/// # use projecture::{project, pin_projectable};
+/// enum Foo {
+///     A { x: usize, y: Box<String> },
+///     B(PhantomPinned, usize),
+///     C,
+/// }
+/// pin_projectable!(Foo);
+///
+/// let mut foo = Foo::A { x: 1, y: Box::new(String::new()) };
+/// project!(match &mut foo {
+///     Foo::A { x, y: *y } => {
+///         let y: &mut String = y;
+///         y.push_str("test");
+///         *x += 1;
+///     }
+///     Foo::B(_, x) => *x += 1,
+///     Foo::C => {}
+/// });
+///
+/// project!(if let Foo::A { x, .. } = &foo {
+///     assert_eq!(*x, 2);
+/// } else {
+///     unreachable!()
+/// });
+///
+/// fn test(foo: Pin<&mut Foo>) -> usize {
+///     project!(let Foo::B(p, x) = foo else { return 0 });
+///     let p: Pin<&mut PhantomPinned> = p;
+///     let x: &mut usize = x;
+///     *x
+/// }
+///
+/// let y = project!(match foo {
+///     Foo::A { y: *y, .. } => y,
+///     _ => String::new(),
+/// });
+/// assert_eq!(y, "test");
+///
+/// let foo = Foo::B(PhantomPinned, 3);
+/// let ptr = &foo as *const Foo;
+/// let x = unsafe { project!(match ptr { Foo::B(_, x) => x.read(), _ => 0 }) };
+/// assert_eq!(x, 3);
+/// ```
+/// Other pattern features: `@` bindings, `&` patterns to copy the value out and `..` in the middle of tuple struct patterns
+/// ```rust
+/// # use std::cell::{Ref, RefCell};
+/// # use projecture::project;
+/// #[derive(Clone, Copy)]
+/// struct Point(i32, i32);
+/// struct Foo(Point, String, usize);
+/// enum Bar {
+///     A(Foo),
+///     B,
+/// }
+/// let foo = RefCell::new(Foo(Point(1, 2), "test".to_string(), 3));
+/// project!(let whole @ Foo(&Point(x, ..), .., last) = foo.borrow());
+/// let whole: Ref<Foo> = whole;
+/// let x: i32 = x;
+/// let last: Ref<usize> = last;
+/// # drop((whole, last));
+///
+/// let bar = Bar::A(foo.into_inner());
+/// let len = project!(match &bar {
+///     Bar::A(Foo(Point(0, _), ..)) => 0,
+///     Bar::A(Foo(Point(1..=10, y), s, _)) => s.len() + *y as usize,
+///     _ => 1,
+/// });
+/// assert_eq!(len, 6);
+/// ```
+/// Array patterns, each item is projected separately and `rest @ ..` projects the remaining items as a slice
+/// ```rust
+/// # use std::cell::Cell;
+/// # use projecture::project;
+/// struct Foo {
+///     buf: [u8; 4],
+///     len: usize,
+/// }
+/// let mut foo = Foo { buf: [1, 2, 3, 4], len: 4 };
+/// project!(let Foo { buf: [first, rest @ .., last], .. } = &mut foo);
+/// let rest: &mut [u8] = rest;
+/// rest[0] = *first;
+/// *last = 0;
+/// assert_eq!(foo.buf, [1, 1, 3, 0]);
+///
+/// let cell = Cell::new([1, 2, 3]);
+/// project!(let [a, _, c] = &cell);
+/// let a: &Cell<i32> = a;
+/// c.set(a.get());
+/// assert_eq!(cell.get(), [1, 2, 1]);
+/// ```
+/// Misuses are compile errors. Unaligned fields of `#[repr(packed)]` structs can be projected only through
+/// raw pointers, other pointers fail with "reference to field of packed struct is unaligned".
+/// ```rust,compile_fail
+/// # use projecture::project;
+/// #[repr(packed)]
+/// struct Packed(u8, usize);
+/// let packed = Packed(1, 2);
+/// let x = project!((&packed) -> 1);
+/// ```
+/// Pinned pointers can be projected only if the type supports pin projection, see [`NotPinProjectable`].
+/// `@` bindings need a pointer that can be [duplicated](Duplicate), so they don't work with `&mut`.
+/// ```rust,compile_fail
+/// # use projecture::project;
+/// struct Foo(usize, usize);
+/// let mut foo = Foo(1, 2);
+/// project!(let whole @ Foo(x, _) = &mut foo);
+/// ```
+/// Pattern forms that can't be projected fail with an error that lists the supported ones,
+/// e.g. or-patterns fail with "or-patterns are not supported by `project!`".
+/// ```rust,compile_fail
+/// # use projecture::project;
+/// enum Foo {
+///     A(usize),
+///     B(usize),
+/// }
+/// let foo = Foo::A(1);
+/// project!(match &foo {
+///     Foo::A(1 | 2) => {}
+///     _ => {}
+/// });
+/// ```
+/// Bindings of destructuring assignment must start with a lowercase letter or `_`, so that an imported
+/// unit variant or a constant is not silently assigned to, otherwise the error mentions `UppercaseBinding<true>`.
+/// ```rust,compile_fail
+/// # use projecture::project;
+/// enum Kind {
+///     Empty,
+/// }
+/// use Kind::Empty;
+/// struct Foo(Kind, usize);
+/// let mut foo = Foo(Kind::Empty, 1);
+/// let x;
+/// project!(Foo(Empty, x) = &mut foo);
+/// ```
 #[macro_export]
 macro_rules! project {
     // ( { $($field:ident),+  } = $target:expr) => {};
+    (match $($tail:tt)+) => {
+        $crate::project_match! { [] $($tail)+ }
+    };
+    (if let $($tail:tt)+) => {
+        $crate::project_if_let! { pat [] $($tail)+ }
+    };
     (let $struct:ident { $($fields:tt)+ } = $val: expr) => {
-        let var = core::mem::ManuallyDrop::new($val);
-        let var = {
-            use $crate::Preprocess;
-            core::mem::ManuallyDrop::new((&&&&&var).preprocess())
-        };
-
-        let (ptr,marker) = {
-            use $crate::Projectable;
-            (&&&&&&& *var).get_raw()
-        };
-        // if false{
-        //     use $crate::AmbiguityCheck;
-        //     let _:() = marker.check();
-        //     // let $struct { .. } = unsafe { &*ptr };
-        // }
-        $crate::project_struct_fields! { [ptr marker $struct] [] $($fields)+ }
-        drop(marker);
+        $crate::project_struct! { [$struct] { $($fields)+ } = $val }
     };
     (let $struct:ident ( $($fields:tt)+ ) = $val: expr) => {
-        let var = core::mem::ManuallyDrop::new($val);
-        let var = {
-            use $crate::Preprocess;
-            core::mem::ManuallyDrop::new((&&&&&var).preprocess())
-        };
-        let (ptr,marker) = {
-            use $crate::Projectable;
-            (&&&&&&& *var).get_raw()
-        };
-        // if false{
-        //     use $crate::AmbiguityCheck;
-        //     let _:() = marker.check();
-        //     // let $struct { .. } = unsafe{ &*ptr };
-        // }
-
-        $crate::project_tuple_fields! { [ptr marker $struct] [] [] $($fields)+ }
-        drop(marker);
+        $crate::project_struct! { [$struct] ( $($fields)+ ) = $val }
+    };
+    (let [ $($elems:tt)* ] = $val: expr) => {
+        $crate::project_array_fields! { [let] [$($elems)*] $val }
     };
     (let * $($tail:tt)+) => {
         $crate::project_deref!{ [] $($tail)+ }
@@ -940,65 +1959,276 @@ macro_rules! project {
     // why the f `let _ = x;` does not drop `x` ?!!
     // and at the same time `let _ = Foo;` does drop `Foo` ... , like wtf?!!
     (let _ = $val:expr) => {
+        #[allow(clippy::drop_non_drop)]
         drop($val);
     };
-    (let $pat:pat = $val:expr) => {
-        let $pat = $val;
+    (let $name:ident = $val:expr) => {
+        let $name = $val;
+    };
+    // also handles `let .. else`
+    (let $($tail:tt)+) => {
+        $crate::project_let_else! { pat [] $($tail)+ }
+    };
+    // destructuring assignment
+    ($($path:ident)::+ { $($fields:tt)* } = $val:expr) => {
+        $crate::project_assign! { [$($path)::+ { $($fields)* }] [$val] [] [] $($path)::+ { $($fields)* } }
+    };
+    ($($path:ident)::+ ( $($fields:tt)* ) = $val:expr) => {
+        $crate::project_assign! { [$($path)::+ ( $($fields)* )] [$val] [] [] $($path)::+ ( $($fields)* ) }
+    };
+    (( $($fields:tt)* ) = $val:expr) => {
+        $crate::project_assign! { [( $($fields)* )] [$val] [] [] ( $($fields)* ) }
+    };
+    ([ $($items:tt)* ] = $val:expr) => {
+        $crate::project_assign! { [[ $($items)* ]] [$val] [] [] [ $($items)* ] }
     };
     ($var:ident ) => { $var };
     ( $var:ident -> $($tail:tt)+) => { $crate::project! { ($var) -> $($tail)+ } };
-    (( $var:expr ) -> $method:tt ($($args:tt)*) $($tail:tt)*) => {
-          //todo
+    ( $var:ident ? $($tail:tt)*) => { $crate::project! { ($var) ? $($tail)* } };
+    // optional chaining, goes inside of the `Option` if the pointer points to one
+    // and flattens it with the `Option` that might be already wrapping the pointer
+    (( $var:expr ) ? $($tail:tt)*) => {
+        {
+            let var = core::mem::ManuallyDrop::new($var);
+            let var = unsafe {
+                use $crate::OptionalChain;
+                (&&var).lift_option()
+            };
+            let var = var.and_then(|var| $crate::project!(match var {
+                core::option::Option::Some(inner) => core::option::Option::Some(inner),
+                _ => core::option::Option::None,
+            }));
+            $crate::project!(var $($tail)*)
+        }
     };
-    (( $var:expr ) -> $field:tt $($tail:tt)*) => {
+    // method is called on the projected pointer and projection continues on whatever it returns
+    (( $var:expr ) -> $method:ident ($($args:tt)*) $($tail:tt)*) => {
+        {
+            let var = ($var).$method($($args)*);
+            $crate::project!(var $($tail)*)
+        }
+    };
+    (( $var:expr ) -> { $($fields:tt)* }) => {
         {
             $crate::project_deref!( ? [ var ] = $var);
-            // use $crate::DoReborrow;
             let var = core::mem::ManuallyDrop::new(var);
-            // let var = unsafe { core::mem::ManuallyDrop::new((&&var).do_reborrow()) };
             let var = {
                 use $crate::Preprocess;
-                core::mem::ManuallyDrop::new((&&&&&var).preprocess())
+                core::mem::ManuallyDrop::new((&&&&&&var).preprocess())
             };
 
             let (ptr,marker) = {
-                use $crate::Projectable;
-                (&&&&&&& *var).get_raw()
+                use $crate::{Projectable,UnwrapTarget};
+                let raw = core::mem::ManuallyDrop::new((&&&&&&& *var).get_raw());
+                unsafe { (&&raw).unwrap_target() }
             };
             let ptr = {
-                // use $crate::AmbiguityCheck;
-                // let _:() = marker.check();
                 use $crate::CheckNoDeref;
-                // check that (*ptr).field would not go through a deref
                 (&&ptr).check_deref()
             };
-
-            $crate::project_field_inner! { [ptr marker] { $field } : temp_name }
-            $crate::project!( temp_name $($tail)*)
+            $crate::project_fields_tuple! { [ptr marker] [] [] [] $($fields)* }
         }
     };
+    (( $var:expr ) -> [ $($index:tt)+ ] $($tail:tt)*) => {
+        {
+            $crate::project_deref!( ? [ var ] = $var);
+            let var = core::mem::ManuallyDrop::new(var);
+            let var = {
+                use $crate::Preprocess;
+                core::mem::ManuallyDrop::new((&&&&&&var).preprocess())
+            };
 
-}
+            let (ptr,marker) = {
+                use $crate::{Projectable,UnwrapTarget};
+                let raw = core::mem::ManuallyDrop::new((&&&&&&& *var).get_raw());
+                unsafe { (&&raw).unwrap_target() }
+            };
+            let ptr = {
+                use $crate::CheckNoDeref;
+                (&&ptr).check_deref()
+            };
 
-#[doc(hidden)]
-#[macro_export]
-macro_rules! project_deref {
-    ( ? [$($parsed:tt)*] = $($tail:tt)* ) => {
-        let var = core::mem::ManuallyDrop::new($($tail)*);
-        let var = {
-            use $crate::Preprocess;
-            (&&&&&var).preprocess()
-        };
+            #[allow(unused_mut)]
+            let mut temp_name = unsafe {
+                use $crate::{IndexProjection,ProjectableMarker,Finalizer};
+                let item_ptr = (&&(ptr, &marker)).index_raw($($index)+);
+                let tmp = core::mem::ManuallyDrop::new(marker.from_raw(item_ptr));
+                (&&&&& tmp).call_finalize()
+            };
+            $crate::project!( temp_name $($tail)*)
+        }
+    };
+    (( $var:expr ) -> $field:tt $($tail:tt)*) => {
+        {
+            $crate::project_deref!( ? [ var ] = $var);
+            // use $crate::DoReborrow;
+            let var = core::mem::ManuallyDrop::new(var);
+            // let var = unsafe { core::mem::ManuallyDrop::new((&&var).do_reborrow()) };
+            let var = {
+                use $crate::Preprocess;
+                core::mem::ManuallyDrop::new((&&&&&&var).preprocess())
+            };
+
+            let (ptr,marker) = {
+                use $crate::{Projectable,UnwrapTarget};
+                let raw = core::mem::ManuallyDrop::new((&&&&&&& *var).get_raw());
+                unsafe { (&&raw).unwrap_target() }
+            };
+            let ptr = {
+                use $crate::CheckNoDeref;
+                // check that (*ptr).field would not go through a deref
+                (&&ptr).check_deref()
+            };
+
+            $crate::project_field_inner! { [ptr marker] { $field } : temp_name }
+            $crate::project!( temp_name $($tail)*)
+        }
+    };
+
+}
+
+// Destructuring assignment, finds the variables that are assigned by walking the pattern
+// (nested patterns are spliced in place) and collects them in order and in reverse.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_assign {
+    ([$($pat:tt)*] [$val:expr] [$($binding:ident)*] [$($reversed:ident)*]) => {
+        {
+            // a bare identifier is always a binding, so unit variants and constants have to be written as paths
+            $(
+                const _: fn() = $crate::assert_binding_name::<
+                    $crate::UppercaseBinding<{ !$crate::is_binding_name(stringify!($binding)) }>,
+                >;
+            )*
+            // bindings of the pattern shadow the variables that are assigned,
+            // so projected values are moved out of the block as a nested tuple
+            let values = {
+                $crate::project!(let $($pat)* = $val);
+                let values = ();
+                $( let values = (values, $binding); )*
+                values
+            };
+            $(
+                let (values, value) = values;
+                $reversed = value;
+            )*
+            let () = values;
+        }
+    };
+    ($pat:tt $val:tt $bindings:tt $reversed:tt ref $($tail:tt)*) => {
+        compile_error!("`ref` and `mut` bindings can't be used in destructuring assignment");
+    };
+    ($pat:tt $val:tt $bindings:tt $reversed:tt mut $($tail:tt)*) => {
+        compile_error!("`ref` and `mut` bindings can't be used in destructuring assignment");
+    };
+    ($pat:tt $val:tt $bindings:tt $reversed:tt true $($tail:tt)*) => {
+        $crate::project_assign! { $pat $val $bindings $reversed $($tail)* }
+    };
+    ($pat:tt $val:tt $bindings:tt $reversed:tt false $($tail:tt)*) => {
+        $crate::project_assign! { $pat $val $bindings $reversed $($tail)* }
+    };
+    // path segments and field names, the last segment of a path is not a binding either
+    ($pat:tt $val:tt $bindings:tt $reversed:tt $name:ident :: $($tail:tt)*) => {
+        $crate::project_assign! { $pat $val $bindings $reversed :: $($tail)* }
+    };
+    ($pat:tt $val:tt $bindings:tt $reversed:tt :: $name:ident $($tail:tt)*) => {
+        $crate::project_assign! { $pat $val $bindings $reversed $($tail)* }
+    };
+    ($pat:tt $val:tt $bindings:tt $reversed:tt $name:ident : $($tail:tt)*) => {
+        $crate::project_assign! { $pat $val $bindings $reversed $($tail)* }
+    };
+    ($pat:tt $val:tt $bindings:tt $reversed:tt $name:ident ( $($inner:tt)* ) $($tail:tt)*) => {
+        $crate::project_assign! { $pat $val $bindings $reversed $($inner)* , $($tail)* }
+    };
+    ($pat:tt $val:tt $bindings:tt $reversed:tt $name:ident { $($inner:tt)* } $($tail:tt)*) => {
+        $crate::project_assign! { $pat $val $bindings $reversed $($inner)* , $($tail)* }
+    };
+    ($pat:tt $val:tt [$($binding:ident)*] [$($reversed:ident)*] $name:ident $($tail:tt)*) => {
+        $crate::project_assign! { $pat $val [$($binding)* $name] [$name $($reversed)*] $($tail)* }
+    };
+    ($pat:tt $val:tt $bindings:tt $reversed:tt ( $($inner:tt)* ) $($tail:tt)*) => {
+        $crate::project_assign! { $pat $val $bindings $reversed $($inner)* , $($tail)* }
+    };
+    ($pat:tt $val:tt $bindings:tt $reversed:tt [ $($inner:tt)* ] $($tail:tt)*) => {
+        $crate::project_assign! { $pat $val $bindings $reversed $($inner)* , $($tail)* }
+    };
+    ($pat:tt $val:tt $bindings:tt $reversed:tt { $($inner:tt)* } $($tail:tt)*) => {
+        $crate::project_assign! { $pat $val $bindings $reversed $($inner)* , $($tail)* }
+    };
+    ($pat:tt $val:tt $bindings:tt $reversed:tt $other:tt $($tail:tt)*) => {
+        $crate::project_assign! { $pat $val $bindings $reversed $($tail)* }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_struct {
+    ([$($path:tt)+] { $($fields:tt)+ } = $val: expr) => {
+        let var = core::mem::ManuallyDrop::new($val);
+        let var = {
+            use $crate::Preprocess;
+            core::mem::ManuallyDrop::new((&&&&&&var).preprocess())
+        };
+
+        let (ptr,marker) = {
+            use $crate::{Projectable,UnwrapTarget};
+            let raw = core::mem::ManuallyDrop::new((&&&&&&& *var).get_raw());
+            unsafe { (&&raw).unwrap_target() }
+        };
+        // fixes the type of the pointer before the fields are projected, fields themselves are checked later
+        if false {
+            let $($path)+ { .. } = unsafe { &*ptr };
+        }
+        $crate::project_struct_fields! { [ptr marker [$($path)+]] [] [] $($fields)+ }
+        #[allow(clippy::drop_non_drop)]
+        drop(marker);
+    };
+    ([$($path:tt)+] ( $($fields:tt)+ ) = $val: expr) => {
+        let var = core::mem::ManuallyDrop::new($val);
+        let var = {
+            use $crate::Preprocess;
+            core::mem::ManuallyDrop::new((&&&&&&var).preprocess())
+        };
+        let (ptr,marker) = {
+            use $crate::{Projectable,UnwrapTarget};
+            let raw = core::mem::ManuallyDrop::new((&&&&&&& *var).get_raw());
+            unsafe { (&&raw).unwrap_target() }
+        };
+        if false {
+            let $($path)+ { .. } = unsafe { &*ptr };
+        }
+        $crate::project_tuple_fields! { [ptr marker [$($path)+]] $($fields)+ }
+        #[allow(clippy::drop_non_drop)]
+        drop(marker);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_deref {
+    ( ? [$($parsed:tt)*] = $($tail:tt)* ) => {
+        let var = core::mem::ManuallyDrop::new($($tail)*);
+        let var = {
+            use $crate::Preprocess;
+            (&&&&&&var).preprocess()
+        };
         let (ptr,marker) = {
             use $crate::DerefProjectable;
             (&&&&&& $crate::MaybeDerefProjectable::new(var)).deref_raw()
         };
         #[allow(unused_mut)]
-        let mut result = unsafe {
-            use $crate::{ProjectableMarker,Finalizer};
+        let (mut result, guard) = unsafe {
+            use $crate::{ProjectableMarker,Finalizer,pin::SelectPinField};
+            // value itself is pinned even if it is a `PinFields!` struct
+            let marker = (&&(core::marker::PhantomData::<$crate::pin::PinnedField>, &marker)).select_marker();
             let tmp = core::mem::ManuallyDrop::new(marker.from_raw(ptr));
-            (&&&&& tmp).call_finalize()
+            let result = (&&&&& tmp).call_finalize();
+            // pinned fields of `replace` are dropped when the projection is finished or on unwind
+            let guard = { use $crate::pin::SelectFieldGuard; (&&(ptr, marker)).field_guard() };
+            (result, guard)
         };
+        unsafe { guard.link() };
+        #[allow(clippy::drop_non_drop)]
         drop(marker);
         $crate::project!(let $($parsed)* = result);
     };
@@ -1007,58 +2237,766 @@ macro_rules! project_deref {
         let var = core::mem::ManuallyDrop::new($($tail)*);
         let var = {
             use $crate::Preprocess;
-            core::mem::ManuallyDrop::new((&&&&&var).preprocess())
+            core::mem::ManuallyDrop::new((&&&&&&var).preprocess())
         };
         let (ptr,marker) = {
             use $crate::DerefProjectable;
             (&&&&&&& *var).deref_raw()
         };
         #[allow(unused_mut)]
-        let mut result = unsafe {
+        let (mut result, guard) = unsafe {
+            use $crate::{ProjectableMarker,Finalizer,pin::SelectPinField};
+            // value itself is pinned even if it is a `PinFields!` struct
+            let marker = (&&(core::marker::PhantomData::<$crate::pin::PinnedField>, &marker)).select_marker();
+            let tmp = core::mem::ManuallyDrop::new(marker.from_raw(ptr));
+            let result = (&&&&& tmp).call_finalize();
+            // pinned fields of `replace` are dropped when the projection is finished or on unwind
+            let guard = { use $crate::pin::SelectFieldGuard; (&&(ptr, marker)).field_guard() };
+            (result, guard)
+        };
+        unsafe { guard.link() };
+        #[allow(clippy::drop_non_drop)]
+        drop(marker);
+        $crate::project!(let $($parsed)* = result);
+    };
+    ([$($parsed:tt)*] $token:tt $($tail:tt)*) => {
+        $crate::project_deref!{ [$($parsed)* $token]  $($tail)* }
+    };
+}
+
+// Gives a reference to the enum behind `$ptr` which is then used to find out the variant and field offsets
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_enum_place {
+    ($ptr:ident $marker:ident $place:ident) => {
+        let $place = {
+            use $crate::{CheckEnum, CheckEnumRaw};
+            // fails to compile if projection does not support enums
+            // and requires `unsafe` for raw pointers
+            (&$marker).check_enum();
+            unsafe { $crate::EnumPlace::new($ptr) }
+        };
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_match {
+    ([$($val:tt)+] { $($arms:tt)* }) => {
+        {
+            let var = core::mem::ManuallyDrop::new($($val)+);
+            let var = {
+                use $crate::Preprocess;
+                core::mem::ManuallyDrop::new((&&&&&&var).preprocess())
+            };
+            let (ptr,marker) = {
+                use $crate::{Projectable,UnwrapTarget};
+                let raw = core::mem::ManuallyDrop::new((&&&&&&& *var).get_raw());
+                unsafe { (&&raw).unwrap_target() }
+            };
+            $crate::project_enum_place!(ptr marker place);
+            $crate::project_match_arms! { [ptr marker place] [] [] $($arms)* }
+        }
+    };
+    ([$($val:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_match! { [$($val)* $next] $($tail)* }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_match_arms {
+    ([$ptr:ident $marker:ident $place:ident] [$($arms:tt)*] [] ) => {
+        match &*$place {
+            $($arms)*
+        }
+    };
+    ([$($args:tt)*] [$($arms:tt)*] [] _ => $($tail:tt)* ) => {
+        $crate::project_match_arms! { body [$($args)*] [$($arms)*] [_] [wildcard] $($tail)* }
+    };
+    ([$($args:tt)*] [$($arms:tt)*] [$($path:tt)+] { $($fields:tt)* } => $($tail:tt)* ) => {
+        $crate::project_match_arms! {
+            body [$($args)*] [$($arms)*] [$crate::project_check_pat!{ pat [$($path)+ { $($fields)* }] }]
+            [project_struct_fields [$($path)+] [] [] $($fields)*] $($tail)*
+        }
+    };
+    ([$($args:tt)*] [$($arms:tt)*] [$($path:tt)+] ( $($fields:tt)* ) => $($tail:tt)* ) => {
+        $crate::project_match_arms! {
+            body [$($args)*] [$($arms)*] [$crate::project_check_pat!{ pat [$($path)+ ( $($fields)* )] }]
+            [project_tuple_fields [$($path)+] $($fields)*] $($tail)*
+        }
+    };
+    ([$($args:tt)*] [$($arms:tt)*] [$name:ident] => $($tail:tt)* ) => {
+        $crate::project_match_arms! { body [$($args)*] [$($arms)*] [$name] [unit $name] $($tail)* }
+    };
+    ([$($args:tt)*] [$($arms:tt)*] [$($path:tt)+] => $($tail:tt)* ) => {
+        $crate::project_match_arms! { body [$($args)*] [$($arms)*] [$($path)+] [] $($tail)* }
+    };
+    ([$($args:tt)*] [$($arms:tt)*] [$($path:tt)*] $next:ident $($tail:tt)* ) => {
+        $crate::project_match_arms! { [$($args)*] [$($arms)*] [$($path)* $next] $($tail)* }
+    };
+    ([$($args:tt)*] [$($arms:tt)*] [$($path:tt)*] :: $($tail:tt)* ) => {
+        $crate::project_match_arms! { [$($args)*] [$($arms)*] [$($path)* ::] $($tail)* }
+    };
+    ([$($args:tt)*] [$($arms:tt)*] [$($path:tt)*] $($tail:tt)* ) => {
+        compile_error!("`project!(match ..)` supports only `_`, `Path`, `Path { .. }` and `Path( .. )` patterns without guards");
+    };
+
+    (body [$($args:tt)*] [$($arms:tt)*] [$($head:tt)*] [$($fields:tt)*] $body:expr , $($tail:tt)*) => {
+        $crate::project_match_arms! { [$($args)*] [$($arms)*
+            // bindings of the check pattern are shadowed by the projected ones
+            #[allow(unused_variables)]
+            $($head)* => { $crate::project_match_arm!{ [$($args)*] $($fields)* } $body }
+        ] [] $($tail)* }
+    };
+    (body [$($args:tt)*] [$($arms:tt)*] [$($head:tt)*] [$($fields:tt)*] $body:block $($tail:tt)*) => {
+        $crate::project_match_arms! { [$($args)*] [$($arms)*
+            // bindings of the check pattern are shadowed by the projected ones
+            #[allow(unused_variables)]
+            $($head)* => { $crate::project_match_arm!{ [$($args)*] $($fields)* } $body }
+        ] [] $($tail)* }
+    };
+    (body [$($args:tt)*] [$($arms:tt)*] [$($head:tt)*] [$($fields:tt)*] $body:expr) => {
+        $crate::project_match_arms! { [$($args)*] [$($arms)*
+            // bindings of the check pattern are shadowed by the projected ones
+            #[allow(unused_variables)]
+            $($head)* => { $crate::project_match_arm!{ [$($args)*] $($fields)* } $body }
+        ] [] }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_if_let {
+    (pat [$($pat:tt)*] = $($tail:tt)*) => {
+        $crate::project_if_let! { val [$($pat)*] [] $($tail)* }
+    };
+    (pat [$($pat:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_if_let! { pat [$($pat)* $next] $($tail)* }
+    };
+    (val [$($pat:tt)*] [$($val:tt)+] $body:block) => {
+        $crate::project_match! { [] $($val)+ { $($pat)* => $body _ => {} } }
+    };
+    (val [$($pat:tt)*] [$($val:tt)+] $body:block else $($else:tt)+) => {
+        $crate::project_match! { [] $($val)+ { $($pat)* => $body _ => { $($else)+ } } }
+    };
+    (val [$($pat:tt)*] [$($val:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_if_let! { val [$($pat)*] [$($val)* $next] $($tail)* }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_let_else {
+    (pat [$($pat:tt)*] = $($tail:tt)*) => {
+        $crate::project_let_else! { val [$($pat)*] [] $($tail)* }
+    };
+    // nested patterns of `project!(match ..)` arms end up here too
+    (pat [$($pat:tt)*] | $($tail:tt)*) => {
+        compile_error!("or-patterns are not supported by `project!`, use separate `project!(match ..)` arms instead");
+    };
+    (pat [$($pat:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_let_else! { pat [$($pat)* $next] $($tail)* }
+    };
+    (val [$($pat:tt)*] [$($val:tt)+] else { $($else:tt)* }) => {
+        let var = core::mem::ManuallyDrop::new($($val)+);
+        let var = {
+            use $crate::Preprocess;
+            core::mem::ManuallyDrop::new((&&&&&&var).preprocess())
+        };
+        let (ptr,marker) = {
+            use $crate::{Projectable,UnwrapTarget};
+            let raw = core::mem::ManuallyDrop::new((&&&&&&& *var).get_raw());
+            unsafe { (&&raw).unwrap_target() }
+        };
+        $crate::project_enum_place!(ptr marker place);
+        $crate::project_let_else! { variant [ptr marker place] [] $($pat)* => { $($else)* } }
+    };
+    (val [$($pat:tt)*] [$($val:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_let_else! { val [$($pat)*] [$($val)* $next] $($tail)* }
+    };
+    // not a `let .. else`
+    (val [& mut $($pat:tt)+] [$($val:tt)+]) => {
+        let tmp = $($val)+;
+        let copied = *tmp;
+        #[allow(clippy::drop_non_drop)]
+        drop(tmp);
+        $crate::project!(let $($pat)+ = copied);
+    };
+    (val [& $($pat:tt)+] [$($val:tt)+]) => {
+        let tmp = $($val)+;
+        let copied = *tmp;
+        #[allow(clippy::drop_non_drop)]
+        drop(tmp);
+        $crate::project!(let $($pat)+ = copied);
+    };
+    (val [$name:ident @ $($pat:tt)+] [$($val:tt)+]) => {
+        let tmp = $($val)+;
+        let $name = $crate::Duplicate::duplicate(&tmp);
+        $crate::project!(let $($pat)+ = tmp);
+    };
+    (val [mut $name:ident @ $($pat:tt)+] [$($val:tt)+]) => {
+        let tmp = $($val)+;
+        let mut $name = $crate::Duplicate::duplicate(&tmp);
+        $crate::project!(let $($pat)+ = tmp);
+    };
+    (val [$($pat:tt)*] [$($val:tt)*]) => {
+        $crate::project_let_else! { struct [] [$($val)*] $($pat)* }
+    };
+    // struct name is a path like `crate::Foo` or `Foo::<u8>`
+    (struct [$($path:tt)+] [$($val:tt)*] { $($fields:tt)+ }) => {
+        $crate::project_struct! { [$($path)+] { $($fields)+ } = $($val)* }
+    };
+    (struct [$($path:tt)+] [$($val:tt)*] ( $($fields:tt)+ )) => {
+        $crate::project_struct! { [$($path)+] ( $($fields)+ ) = $($val)* }
+    };
+    (struct [$($path:tt)*] [$($val:tt)*] $next:tt $($tail:tt)+) => {
+        $crate::project_let_else! { struct [$($path)* $next] [$($val)*] $($tail)+ }
+    };
+    (struct [$($path:tt)*] [$($val:tt)*] $($tail:tt)*) => {
+        let $($path)* $($tail)* = $($val)*;
+    };
+
+    (variant [$ptr:ident $marker:ident $place:ident] [$($path:tt)+] { $($fields:tt)* } => { $($else:tt)* }) => {
+        $crate::project_let_else! { check [$ptr $marker $place] [$crate::project_check_pat!{ pat [$($path)+ { $($fields)* }] }] { $($else)* } }
+        $crate::project_variant_fields! { [$ptr $marker $place [$($path)+]] project_struct_fields [] [] $($fields)* }
+        #[allow(clippy::drop_non_drop)]
+        drop($marker);
+    };
+    (variant [$ptr:ident $marker:ident $place:ident] [$($path:tt)+] ( $($fields:tt)* ) => { $($else:tt)* }) => {
+        $crate::project_let_else! { check [$ptr $marker $place] [$crate::project_check_pat!{ pat [$($path)+ ( $($fields)* )] }] { $($else)* } }
+        $crate::project_variant_fields! { [$ptr $marker $place [$($path)+]] project_tuple_fields $($fields)* }
+        #[allow(clippy::drop_non_drop)]
+        drop($marker);
+    };
+    (variant [$($args:tt)*] [$name:ident] => { $($else:tt)* }) => {
+        $crate::project_let_else! { check [$($args)*] [$name] { $($else)* } }
+        $crate::project_match_arm! { [$($args)*] unit $name }
+    };
+    (variant [$($args:tt)*] [$($path:tt)+] => { $($else:tt)* }) => {
+        $crate::project_let_else! { check [$($args)*] [$($path)+] { $($else)* } }
+        $crate::project_match_arm! { [$($args)*] }
+    };
+    (variant [$($args:tt)*] [$($path:tt)*] $next:ident $($tail:tt)*) => {
+        $crate::project_let_else! { variant [$($args)*] [$($path)* $next] $($tail)* }
+    };
+    (variant [$($args:tt)*] [$($path:tt)*] :: $($tail:tt)*) => {
+        $crate::project_let_else! { variant [$($args)*] [$($path)* ::] $($tail)* }
+    };
+    (variant [$($args:tt)*] [$($path:tt)*] $($tail:tt)*) => {
+        compile_error!("`project!(let .. else ..)` supports only `Path`, `Path { .. }` and `Path( .. )` patterns");
+    };
+    (check [$ptr:ident $marker:ident $place:ident] [$($head:tt)+] { $($else:tt)* }) => {
+        #[allow(unreachable_patterns)]
+        match &*$place {
+            #[allow(unused_variables)]
+            $($head)+ => {}
+            _ => {
+                $crate::project_match_arm! { [$ptr $marker $place] wildcard }
+                // `else` block must diverge
+                #[allow(unreachable_code, clippy::diverging_sub_expression)]
+                let _: core::convert::Infallible = { $($else)* };
+            }
+        }
+    };
+}
+
+// projects fields of the matched variant
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_match_arm {
+    ([$ptr:ident $marker:ident $place:ident] wildcard) => {
+        // nothing was destructured so the value is dropped as a whole
+        #[allow(clippy::drop_non_drop)]
+        drop(unsafe {
+            use $crate::ProjectableMarker;
+            $marker.from_raw($ptr)
+        });
+        #[allow(clippy::drop_non_drop)]
+        drop($marker);
+    };
+    ([$ptr:ident $marker:ident $place:ident] unit $name:ident) => {
+        // check that `$name` is a unit variant rather than a binding to the enum place
+        fn type_of<T>(_: &T) -> core::marker::PhantomData<T> {
+            core::marker::PhantomData
+        }
+        let _ = [type_of(&$name), type_of(&*$place)];
+        #[allow(clippy::drop_non_drop)]
+        drop($marker);
+    };
+    ([$ptr:ident $marker:ident $place:ident] $macro:ident [$($path:tt)+] $($fields:tt)*) => {
+        $crate::project_variant_fields! { [$ptr $marker $place [$($path)+]] $macro $($fields)* }
+        #[allow(clippy::drop_non_drop)]
+        drop($marker);
+    };
+    ([$ptr:ident $marker:ident $place:ident]) => {
+        #[allow(clippy::drop_non_drop)]
+        drop($marker);
+    };
+}
+
+// Finds pointers to all fields of the matched variant at once, before any of them is projected,
+// because reading the enum again would invalidate the fields that are already borrowed mutably.
+// Each field pattern is then prefixed with the `[field]` pointer that `project_field_inner!` projects.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_variant_fields {
+    ($args:tt project_tuple_fields $($fields:tt)*) => {
+        $crate::project_variant_fields! { tuple $args [] [] [] [] $($fields)* }
+    };
+    ($args:tt project_struct_fields [] [] $($fields:tt)*) => {
+        $crate::project_variant_fields! { struct $args [] [] [] [] $($fields)* }
+    };
+    (tuple $args:tt [$($binds:tt)*] [$($ptrs:ident)*] [$($fields:tt)*] [] .. $(, $($tail:tt)*)?) => {
+        $crate::project_variant_fields! { tuple $args [$($binds)* ..,] [$($ptrs)*] [$($fields)* ..,] [] $($($tail)*)? }
+    };
+    (tuple $args:tt [$($binds:tt)*] [$($ptrs:ident)*] [$($fields:tt)*] [] $pattern:tt , $($tail:tt)*) => {
+        $crate::project_variant_fields! { tuple $args [$($binds)* field,] [$($ptrs)* field] [$($fields)* [field] $pattern,] [] $($tail)* }
+    };
+    (tuple $args:tt [$($binds:tt)*] [$($ptrs:ident)*] [$($fields:tt)*] [$($pattern:tt)+] , $($tail:tt)*) => {
+        $crate::project_variant_fields! { tuple $args [$($binds)* field,] [$($ptrs)* field] [$($fields)* [field] $($pattern)+,] [] $($tail)* }
+    };
+    (tuple [$ptr:ident $marker:ident $place:ident [$($path:tt)+]] [$($binds:tt)*] [$($ptrs:ident)*] [$($fields:tt)*] []) => {
+        let ($($ptrs,)*) = $crate::project_variant_fields! { ptrs [$ptr $place] [$($path)+ ( $($binds)* )] $($ptrs)* };
+        $crate::project_tuple_fields! { [$ptr $marker variant] $($fields)* }
+    };
+    // `..` must be the last field of a struct pattern
+    (struct $args:tt [$($binds:tt)*] [$($ptrs:ident)*] [$($fields:tt)*] [] .. $(,)?) => {
+        $crate::project_variant_fields! { struct $args [$($binds)* ..] [$($ptrs)*] [$($fields)* ..] [] }
+    };
+    (struct $args:tt [$($binds:tt)*] [$($ptrs:ident)*] [$($fields:tt)*] [] $name:tt , $($tail:tt)*) => {
+        $crate::project_variant_fields! { struct $args [$($binds)* $name: field,] [$($ptrs)* field] [$($fields)* $name: [field] $name,] [] $($tail)* }
+    };
+    (struct $args:tt [$($binds:tt)*] [$($ptrs:ident)*] [$($fields:tt)*] [$name:tt : $($pattern:tt)+] , $($tail:tt)*) => {
+        $crate::project_variant_fields! { struct $args [$($binds)* $name: field,] [$($ptrs)* field] [$($fields)* $name: [field] $($pattern)+,] [] $($tail)* }
+    };
+    (struct $args:tt $binds:tt $ptrs:tt $fields:tt [$name:tt] , $($tail:tt)*) => {
+        $crate::project_variant_fields! { struct $args $binds $ptrs $fields [] $name, $($tail)* }
+    };
+    (struct $args:tt $binds:tt $ptrs:tt $fields:tt [$($pattern:tt)+] , $($tail:tt)*) => {
+        compile_error!("fields of struct patterns can only be `field`, `field: pattern` or `..`");
+    };
+    (struct [$ptr:ident $marker:ident $place:ident [$($path:tt)+]] [$($binds:tt)*] [$($ptrs:ident)*] [$($fields:tt)*] []) => {
+        let ($($ptrs,)*) = $crate::project_variant_fields! { ptrs [$ptr $place] [$($path)+ { $($binds)* }] $($ptrs)* };
+        $crate::project_struct_fields! { [$ptr $marker variant] [] [] $($fields)* }
+    };
+    ($kind:ident $args:tt $binds:tt $ptrs:tt $fields:tt [$($pattern:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_variant_fields! { $kind $args $binds $ptrs $fields [$($pattern)* $next] $($tail)* }
+    };
+    ($kind:ident $args:tt $binds:tt $ptrs:tt $fields:tt [$($pattern:tt)+]) => {
+        $crate::project_variant_fields! { $kind $args $binds $ptrs $fields [$($pattern)+] , }
+    };
+    (ptrs [$ptr:ident $place:ident] [$($pattern:tt)+] $($ptrs:ident)*) => {{
+        let place = &*$place;
+        #[allow(unreachable_patterns)]
+        match place {
+            $($pattern)+ => ($($crate::variant_field_ptr($ptr, place, $ptrs),)*),
+            _ => unreachable!(),
+        }
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_tuple_fields {
+    (state [$($args:tt)*] [$($moved:tt)*] [$($indexes:tt)*] [] .. $(,)? ) => {
+        $crate::project_tuple_fields! { check [$($args)*] [$($moved)*] [$($indexes)*] }
+        $crate::project_drop_rest! { tuple [$($args)*] [$($moved)*] }
+    };
+    // fields after `..` are projected by their position from the end
+    (state [$($args:tt)*] [$($moved:tt)*] [$($indexes:tt)*] [] .. , $($tail:tt)+ ) => {
+        $crate::project_tuple_fields! { check [$($args)*] [$($moved)*] [$($indexes)*] }
+        $crate::project_tuple_fields! { tail [$($args)*] [$($moved)*] [] [] $($tail)+ }
+    };
+    // runs of `_` and plain bindings are projected 16 at a time, so that the recursion depth stays small
+    // for tuples with many fields, other fields are split token by token
+    (state $args:tt [$($moved:tt)*] [$i0:tt $i1:tt $i2:tt $i3:tt $i4:tt $i5:tt $i6:tt $i7:tt $i8:tt $i9:tt $i10:tt $i11:tt $i12:tt $i13:tt $i14:tt $i15:tt $($indexes:tt)*] [] _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, $($tail:tt)*) => {
+        $crate::project_tuple_fields! { fields $args [($i0 _) ($i1 _) ($i2 _) ($i3 _) ($i4 _) ($i5 _) ($i6 _) ($i7 _) ($i8 _) ($i9 _) ($i10 _) ($i11 _) ($i12 _) ($i13 _) ($i14 _) ($i15 _)] }
+        $crate::project_tuple_fields! { state $args [$($moved)* $i0 $i1 $i2 $i3 $i4 $i5 $i6 $i7 $i8 $i9 $i10 $i11 $i12 $i13 $i14 $i15] [$($indexes)*] [] $($tail)* }
+    };
+    (state $args:tt [$($moved:tt)*] [$i0:tt $i1:tt $i2:tt $i3:tt $i4:tt $i5:tt $i6:tt $i7:tt $i8:tt $i9:tt $i10:tt $i11:tt $i12:tt $i13:tt $i14:tt $i15:tt $($indexes:tt)*] [] $f0:ident, $f1:ident, $f2:ident, $f3:ident, $f4:ident, $f5:ident, $f6:ident, $f7:ident, $f8:ident, $f9:ident, $f10:ident, $f11:ident, $f12:ident, $f13:ident, $f14:ident, $f15:ident, $($tail:tt)*) => {
+        $crate::project_tuple_fields! { fields $args [($i0 $f0) ($i1 $f1) ($i2 $f2) ($i3 $f3) ($i4 $f4) ($i5 $f5) ($i6 $f6) ($i7 $f7) ($i8 $f8) ($i9 $f9) ($i10 $f10) ($i11 $f11) ($i12 $f12) ($i13 $f13) ($i14 $f14) ($i15 $f15)] }
+        $crate::project_tuple_fields! { state $args [$($moved)* $i0 $i1 $i2 $i3 $i4 $i5 $i6 $i7 $i8 $i9 $i10 $i11 $i12 $i13 $i14 $i15] [$($indexes)*] [] $($tail)* }
+    };
+    (state $args:tt [$($moved:tt)*] [$index:tt $($indexes:tt)*] [] _ , $($tail:tt)*) => {
+        $crate::project_field_inner! { $args { $index } : _ }
+        $crate::project_tuple_fields! { state $args [$($moved)* $index] [$($indexes)*] [] $($tail)* }
+    };
+    (state $args:tt [$($moved:tt)*] [$index:tt $($indexes:tt)*] [] $field:ident , $($tail:tt)*) => {
+        $crate::project_field_inner! { $args { $index } : $field }
+        $crate::project_tuple_fields! { state $args [$($moved)* $index] [$($indexes)*] [] $($tail)* }
+    };
+    (state [$($args:tt)*] [$($moved:tt)*] [$index:tt $($indexes:tt)*] [$($pattern:tt)*] , $($tail:tt)* ) => {
+        $crate::project_field_inner! { [$($args)*] { $index } : $($pattern)* }
+        $crate::project_tuple_fields! { state [$($args)*] [$($moved)* $index] [$($indexes)*] [] $($tail)* }
+    };
+    // out of literal indexes, so the field is found by its position
+    (state $args:tt [$($moved:tt)*] [] [] _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, $($tail:tt)*) => {
+        $crate::project_field_inner! { $args { + $($moved)* } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ _ _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ _ _ _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ _ _ _ _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ _ _ _ _ _ } : _ }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ } : _ }
+        $crate::project_tuple_fields! { state $args [$($moved)* _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _] [] [] $($tail)* }
+    };
+    (state $args:tt [$($moved:tt)*] [] [] $f0:ident, $f1:ident, $f2:ident, $f3:ident, $f4:ident, $f5:ident, $f6:ident, $f7:ident, $f8:ident, $f9:ident, $f10:ident, $f11:ident, $f12:ident, $f13:ident, $f14:ident, $f15:ident, $($tail:tt)*) => {
+        $crate::project_field_inner! { $args { + $($moved)* } : $f0 }
+        $crate::project_field_inner! { $args { + $($moved)* _ } : $f1 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ } : $f2 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ } : $f3 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ } : $f4 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ } : $f5 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ } : $f6 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ } : $f7 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ } : $f8 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ } : $f9 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ _ } : $f10 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ _ _ } : $f11 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ _ _ _ } : $f12 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ _ _ _ _ } : $f13 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ _ _ _ _ _ } : $f14 }
+        $crate::project_field_inner! { $args { + $($moved)* _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ } : $f15 }
+        $crate::project_tuple_fields! { state $args [$($moved)* _ _ _ _ _ _ _ _ _ _ _ _ _ _ _ _] [] [] $($tail)* }
+    };
+    (state $args:tt [$($moved:tt)*] [] [] _ , $($tail:tt)*) => {
+        $crate::project_field_inner! { $args { + $($moved)* } : _ }
+        $crate::project_tuple_fields! { state $args [$($moved)* _] [] [] $($tail)* }
+    };
+    (state $args:tt [$($moved:tt)*] [] [] $field:ident , $($tail:tt)*) => {
+        $crate::project_field_inner! { $args { + $($moved)* } : $field }
+        $crate::project_tuple_fields! { state $args [$($moved)* _] [] [] $($tail)* }
+    };
+    (state [$($args:tt)*] [$($moved:tt)*] [] [$($pattern:tt)*] , $($tail:tt)* ) => {
+        $crate::project_field_inner! { [$($args)*] { + $($moved)* } : $($pattern)* }
+        $crate::project_tuple_fields! { state [$($args)*] [$($moved)* _] [] [] $($tail)* }
+    };
+    (state [$($args:tt)*] [$($moved:tt)*] [$($indexes:tt)*] [] ) => {
+        $crate::project_tuple_fields! { check [$($args)*] [$($moved)*] [$($indexes)*] }
+    };
+    (state [$($args:tt)*] [$($moved:tt)*] [$($indexes:tt)*] [$($pattern:tt)*] $next:tt $($tail:tt)* ) => {
+        $crate::project_tuple_fields! { state [$($args)*] [$($moved)*] [$($indexes)*] [$($pattern)* $next] $($tail)*  }
+    };
+    (state [$($args:tt)*] [$($moved:tt)*] [$($indexes:tt)*] [$($pattern:tt)+] ) => {
+        $crate::project_tuple_fields! { state [$($args)*] [$($moved)*] [$($indexes)*] [$($pattern)+] , }
+    };
+
+    (tail [$($args:tt)*] [$($moved:tt)*] [$($fields:tt)*] [$($pattern:tt)*] , $($tail:tt)*) => {
+        $crate::project_tuple_fields! { tail [$($args)*] [$($moved)*] [$($fields)* ($($pattern)*)] [] $($tail)* }
+    };
+    (tail [$($args:tt)*] [$($moved:tt)*] [$($fields:tt)*] [$($pattern:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_tuple_fields! { tail [$($args)*] [$($moved)*] [$($fields)*] [$($pattern)* $next] $($tail)* }
+    };
+    (tail [$($args:tt)*] [$($moved:tt)*] [$($fields:tt)*] [$($pattern:tt)+]) => {
+        $crate::project_tuple_fields! { from_end [$($args)*] [$($moved)*] [] $($fields)* ($($pattern)+) }
+    };
+    (tail [$($args:tt)*] [$($moved:tt)*] [$($fields:tt)*] []) => {
+        $crate::project_tuple_fields! { from_end [$($args)*] [$($moved)*] [] $($fields)* }
+    };
+    (fields $args:tt [$(($index:tt $pattern:tt))*]) => {
+        $( $crate::project_field_inner! { $args { $index } : $pattern } )*
+    };
+    // each field is followed by `$rest` fields
+    (from_end [$($args:tt)*] [$($moved:tt)*] [$($moved_end:tt)*] ($($pattern:tt)*) $($rest:tt)*) => {
+        $crate::project_field_inner! { [$($args)*] { - $($rest)* } : $($pattern)* }
+        $crate::project_tuple_fields! { from_end [$($args)*] [$($moved)*] [$($moved_end)* [$($rest)*]] $($rest)* }
+    };
+    (from_end [$($args:tt)*] [$($moved:tt)*] [$($moved_end:tt)*]) => {
+        $crate::project_drop_rest! { tuple [$($args)*] [$($moved)*] $($moved_end)* }
+    };
+
+    // all indexes must be fields of the struct, this is checked in one pattern instead of one per field.
+    // Once the indexes run out, fields are found with positional patterns that check themselves
+    (check [$ptr:ident $marker:ident [$($type:tt)+]] [$($moved:tt)*] []) => {
+        if false {
+            let $($type)+ { 255: _, .. } = unsafe { &*$ptr };
+        }
+    };
+    (check [$ptr:ident $marker:ident [$($type:tt)+]] [$($moved:tt)*] [$($indexes:tt)+]) => {
+        if false {
+            let $($type)+ { $($moved: _,)* .. } = unsafe { &*$ptr };
+        }
+    };
+    (check $args:tt $moved:tt $indexes:tt) => {};
+
+    ([$($args:tt)*] $($fields:tt)*) => {
+        $crate::with_tuple_indexes! { project_tuple_fields [state [$($args)*] []] [] $($fields)* }
+    };
+}
+
+// Projects items of an array pattern, items after `..` are found by their position from the end.
+// In `nested` mode the pattern is already known to match, so items can be refutable.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_array_fields {
+    ([$mode:ident] [$($elems:tt)*] $val:expr) => {
+        let var = core::mem::ManuallyDrop::new($val);
+        let var = {
+            use $crate::Preprocess;
+            core::mem::ManuallyDrop::new((&&&&&&var).preprocess())
+        };
+        let (ptr,marker) = {
+            use $crate::{Projectable,UnwrapTarget};
+            let raw = core::mem::ManuallyDrop::new((&&&&&&& *var).get_raw());
+            unsafe { (&&raw).unwrap_target() }
+        };
+        $crate::project_array_fields! { check [ptr $mode] $($elems)* }
+        $crate::with_tuple_indexes! { project_array_fields [state [ptr marker $mode]] [] $($elems)* }
+        #[allow(clippy::drop_non_drop)]
+        drop(marker);
+    };
+    // checks that array is long enough (and that it is not a slice, which would make the pattern refutable)
+    (check [$ptr:ident let] $($elems:tt)*) => {
+        #[allow(unused_variables)]
+        if false {
+            let $crate::project_check_pat!{ pat [[$($elems)*]] } = unsafe { &*$ptr };
+        }
+    };
+    (check [$ptr:ident nested] $($elems:tt)*) => {};
+
+    (state [$($args:tt)*] [$before:tt $($indexes:tt)*] [] .. $(, $($tail:tt)*)?) => {
+        $crate::with_tuple_indexes! { project_array_fields [tail [$($args)*] [$before] []] [] [] $($($tail)*)? }
+    };
+    (state [$($args:tt)*] [$before:tt $($indexes:tt)*] [] $name:ident @ .. $(, $($tail:tt)*)?) => {
+        $crate::with_tuple_indexes! { project_array_fields [tail [$($args)*] [$before] [$name]] [] [] $($($tail)*)? }
+    };
+    (state [$($args:tt)*] [$index:tt $($indexes:tt)*] [$($pattern:tt)*] , $($tail:tt)*) => {
+        $crate::project_array_fields! { item [$($args)*] [$index, false] $($pattern)* }
+        $crate::project_array_fields! { state [$($args)*] [$($indexes)*] [] $($tail)* }
+    };
+    (state [$($args:tt)*] [] [$($pattern:tt)*] , $($tail:tt)*) => {
+        compile_error!("array patterns are supported only up to 256 items");
+    };
+    (state [$($args:tt)*] [$($indexes:tt)*] []) => {};
+    (state [$($args:tt)*] [$($indexes:tt)*] [$($pattern:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_array_fields! { state [$($args)*] [$($indexes)*] [$($pattern)* $next] $($tail)* }
+    };
+    (state [$($args:tt)*] [$($indexes:tt)*] [$($pattern:tt)+]) => {
+        $crate::project_array_fields! { state [$($args)*] [$($indexes)*] [$($pattern)+] , }
+    };
+
+    // items after `..` are collected together with their position in the tail
+    (tail [$($args:tt)*] [$before:tt] [$($name:ident)?] [$index:tt $($indexes:tt)*] [$($items:tt)*] [$($pattern:tt)*] , $($tail:tt)*) => {
+        $crate::project_array_fields! {
+            tail [$($args)*] [$before] [$($name)?] [$($indexes)*] [$($items)* ($index [$($pattern)*])] [] $($tail)*
+        }
+    };
+    (tail [$($args:tt)*] [$before:tt] [$($name:ident)?] [] [$($items:tt)*] [$($pattern:tt)*] , $($tail:tt)*) => {
+        compile_error!("array patterns are supported only up to 256 items");
+    };
+    (tail [$($args:tt)*] [$before:tt] [$($name:ident)?] [$($indexes:tt)*] [$($items:tt)*] [$($pattern:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_array_fields! {
+            tail [$($args)*] [$before] [$($name)?] [$($indexes)*] [$($items)*] [$($pattern)* $next] $($tail)*
+        }
+    };
+    (tail [$($args:tt)*] [$before:tt] [$($name:ident)?] [$($indexes:tt)*] [$($items:tt)*] [$($pattern:tt)+]) => {
+        $crate::project_array_fields! {
+            tail [$($args)*] [$before] [$($name)?] [$($indexes)*] [$($items)*] [$($pattern)+] ,
+        }
+    };
+    (tail [$ptr:ident $marker:ident $mode:ident] [$before:tt] [$($name:ident)?] [$after:tt $($indexes:tt)*] [$(($index:tt [$($pattern:tt)*]))*] []) => {
+        $( $crate::project_array_fields! { item [$ptr $marker $mode] [$after - $index, true] $($pattern)* } )*
+        $crate::project_array_fields! { rest [$ptr $marker $mode] [$before, $after] $($name)? }
+    };
+
+    (item [$ptr:ident $marker:ident $mode:ident] [$($index:tt)*] $($pattern:tt)*) => {
+        #[allow(unused_mut)]
+        let mut tmp = unsafe {
+            use $crate::{ProjectableMarker,Finalizer};
+            let item_ptr = $crate::ArrayLike::item_ptr($ptr, $($index)*);
+            let tmp = core::mem::ManuallyDrop::new($marker.from_raw(item_ptr));
+            (&&&&& tmp).call_finalize()
+        };
+        $crate::project_array_fields! { bind $mode [$($pattern)*] tmp }
+    };
+    (rest [$ptr:ident $marker:ident $mode:ident] [$($counts:tt)*] $name:ident) => {
+        #[allow(unused_mut)]
+        let mut $name = unsafe {
             use $crate::{ProjectableMarker,Finalizer};
-            let tmp = core::mem::ManuallyDrop::new(marker.from_raw(ptr));
+            let rest_ptr = $crate::ArrayLike::rest_ptr($ptr, $($counts)*);
+            let tmp = core::mem::ManuallyDrop::new($marker.from_raw(rest_ptr));
             (&&&&& tmp).call_finalize()
         };
-        drop(marker);
-        $crate::project!(let $($parsed)* = result);
     };
-    ([$($parsed:tt)*] $token:tt $($tail:tt)*) => {
-        $crate::project_deref!{ [$($parsed)* $token]  $($tail)* }
+    // drops the items that were not mentioned, if the projection is an owned one
+    (rest [$ptr:ident $marker:ident $mode:ident] [$($counts:tt)*]) => {
+        unsafe {
+            use $crate::DropRemainingFields;
+            let rest_ptr = $crate::ArrayLike::rest_ptr($ptr, $($counts)*);
+            (&&(rest_ptr, &$marker)).drop_remaining::<()>();
+        }
+    };
+    (bind let [$($pattern:tt)*] $val:ident) => {
+        $crate::project!(let $($pattern)* = $val);
+    };
+    (bind nested [$($pattern:tt)*] $val:ident) => {
+        $crate::project_nested! { [$($pattern)*] $val }
     };
 }
 
+// Passes literals that are used as tuple field indexes into `$callback` macro,
+// tuple fields after these are still supported but only via patterns
 #[doc(hidden)]
 #[macro_export]
-macro_rules! project_tuple_fields {
-    ([$ptr:ident $marker:ident $struct:ident] [$($idx:tt)*] [$($pattern:tt)*] , $($tail:tt)* ) => {
-        $crate::project_field_inner! { [$ptr $marker $struct] { $($idx)* } : $($pattern)* }
-        $crate::project_tuple_fields! { [$ptr $marker $struct] [$($idx)* !] [] $($tail)* }
+macro_rules! with_tuple_indexes {
+    ($callback:ident [$($before:tt)*] $($after:tt)*) => {
+        $crate::$callback! { $($before)* [
+            0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31
+            32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60 61 62 63
+            64 65 66 67 68 69 70 71 72 73 74 75 76 77 78 79 80 81 82 83 84 85 86 87 88 89 90 91 92 93 94 95
+            96 97 98 99 100 101 102 103 104 105 106 107 108 109 110 111 112 113 114 115 116 117 118 119 120 121 122 123 124 125 126 127
+            128 129 130 131 132 133 134 135 136 137 138 139 140 141 142 143 144 145 146 147 148 149 150 151 152 153 154 155 156 157 158 159
+            160 161 162 163 164 165 166 167 168 169 170 171 172 173 174 175 176 177 178 179 180 181 182 183 184 185 186 187 188 189 190 191
+            192 193 194 195 196 197 198 199 200 201 202 203 204 205 206 207 208 209 210 211 212 213 214 215 216 217 218 219 220 221 222 223
+            224 225 226 227 228 229 230 231 232 233 234 235 236 237 238 239 240 241 242 243 244 245 246 247 248 249 250 251 252 253 254 255
+        ] $($after)* }
     };
-    ([$ptr:ident $marker:ident $struct:ident] [$($idx:tt)*] [] ) => {};
-    ([$ptr:ident $marker:ident $struct:ident] [$($idx:tt)*] [] .. ) => {};
+}
 
-    ([$ptr:ident $marker:ident $struct:ident] [$($idx:tt)*] [$($pattern:tt)*]  $next:tt $($tail:tt)* ) => {
-        $crate::project_tuple_fields! { [$ptr $marker $struct] [$($idx)*] [$($pattern)* $next] $($tail)*  }
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_struct_fields {
+    // lists where every field is `name` or `name: pattern` with a pattern like `binding`, `_`, `[..]`,
+    // `Path { .. }` or `Path( .. )` are split in one step instead of token by token,
+    // so that the recursion depth does not grow with the number of fields.
+    // Other patterns (`&x`, `ref x`, `x @ ..`) are split token by token
+    ($args:tt [$($moved:tt)*] [] $($name:ident $(: $head:tt $(:: $path:ident)* $(( $($tuple:tt)* ))? $({ $($named:tt)* })?)?),+ $(,)?) => {
+        $crate::project_struct_fields! { prepare [offsets] $args [$($name)+] }
+        $( $crate::project_field_inner! { $args { $name in offsets } $(: $head $(:: $path)* $(( $($tuple)* ))? $({ $($named)* })?)? } )+
+        $crate::project_struct_fields! { check $args [$($moved)* $($name)+] }
+    };
+    ($args:tt [$($moved:tt)*] [] $($name:ident $(: $head:tt $(:: $path:ident)* $(( $($tuple:tt)* ))? $({ $($named:tt)* })?)?),+ , ..) => {
+        $crate::project_struct_fields! { prepare [offsets] $args [$($name)+] }
+        $( $crate::project_field_inner! { $args { $name in offsets } $(: $head $(:: $path)* $(( $($tuple)* ))? $({ $($named)* })?)? } )+
+        $crate::project_struct_fields! { check $args [$($moved)* $($name)+] }
+        $crate::project_drop_rest! { $args [$((stringify!($moved)))* $((stringify!($name)))+] }
+    };
+    ($args:tt [$($moved:tt)*] [] $name:ident $(: $head:tt $(:: $path:ident)* $(( $($tuple:tt)* ))? $({ $($named:tt)* })?)? , $($tail:tt)+) => {
+        $crate::project_field_inner! { $args { $name } $(: $head $(:: $path)* $(( $($tuple)* ))? $({ $($named)* })?)? }
+        $crate::project_struct_fields! { $args [$($moved)* $name] [] $($tail)+ }
     };
-    ([$ptr:ident $marker:ident $struct:ident] [$($idx:tt)*] [$($pattern:tt)*] ) => {
-        $crate::project_field_inner! { [$ptr $marker $struct] { $($idx)* } : $($pattern)* }
+    ([$($args:tt)*] [$($moved:tt)*] [$name:tt $($pattern:tt)*] , $($tail:tt)* ) => {
+        $crate::project_field_inner! { [$($args)*] { $name } $($pattern)* }
+        $crate::project_struct_fields! { [$($args)*] [$($moved)* $name] [] $($tail)* }
     };
+    ([$($args:tt)*] [$($moved:tt)*] [] ) => {
+        $crate::project_struct_fields! { check [$($args)*] [$($moved)*] }
+    };
+    ([$($args:tt)*] [$($moved:tt)*] [] ..) => {
+        $crate::project_struct_fields! { check [$($args)*] [$($moved)*] }
+        $crate::project_drop_rest! { [$($args)*] [$((stringify!($moved)))*] }
+    };
+    ([$($args:tt)*] [$($moved:tt)*] [$($pattern:tt)*] $next:tt $($tail:tt)* ) => {
+        $crate::project_struct_fields! { [$($args)*] [$($moved)*] [$($pattern)* $next] $($tail)*  }
+    };
+    ([$($args:tt)*] [$($moved:tt)*] [$($pattern:tt)+] ) => {
+        $crate::project_struct_fields! { [$($args)*] [$($moved)*] [$($pattern)+] , }
+    };
+    // every field must be mentioned only once, otherwise projections would alias,
+    // variant patterns are already checked by the match on the enum place
+    (check [$ptr:ident $marker:ident [$($type:tt)+]] [$($moved:tt)*]) => {
+        if false {
+            let $($type)+ { $($moved: _,)* .. } = unsafe { &*$ptr };
+        }
+    };
+    (check [$($args:tt)*] [$($moved:tt)*]) => {};
+    // checks for `#[repr(packed)]` and finds offsets for pointers that may dangle (see `uninit_offsets`)
+    // for all fields of the pattern at once, instead of in each field
+    (prepare [$offsets:ident] [$ptr:ident $marker:ident [$($type:tt)+]] [$($name:ident)+]) => {
+        #[allow(unused_unsafe)]
+        let $offsets = unsafe {
+            $crate::project_field_inner!(packed $ptr $marker [$($name)+]);
+            let may_dangle = { use $crate::MayDangle; (&&(&$marker,)).may_dangle() };
+            $crate::uninit_offsets($ptr, may_dangle, |base| [$(core::ptr::addr_of_mut!((*base). $name) as *mut u8),+])
+        };
+        let mut $offsets = $offsets.iter();
+    };
+    (prepare $offsets:tt $args:tt $names:tt) => {};
 }
 
+// Projects several fields at once into a tuple, like `project!(x -> {a, b: *c})`.
+// Each field is bound to its own hygienic `field` ident which is then passed along to build the tuple.
 #[doc(hidden)]
 #[macro_export]
-macro_rules! project_struct_fields {
-    ([$ptr:ident $marker:ident $struct:ident] [$name:tt $($pattern:tt)*] , $($tail:tt)* ) => {
-        $crate::project_field_inner! { [$ptr $marker $struct] { $name } $($pattern)* }
-        $crate::project_struct_fields! { [$ptr $marker $struct]  [] $($tail)* }
+macro_rules! project_fields_tuple {
+    ([$($args:tt)*] [$($names:tt)*] [$($bindings:tt)*] [$name:tt] , $($tail:tt)*) => {
+        $crate::project_field_inner! { [$($args)*] { $name } : field }
+        $crate::project_fields_tuple! { [$($args)*] [$($names)* $name] [$($bindings)* field] [] $($tail)* }
+    };
+    ([$($args:tt)*] [$($names:tt)*] [$($bindings:tt)*] [$name:tt : $($pattern:tt)+] , $($tail:tt)*) => {
+        $crate::project_fields_tuple! { pattern [$($args)*] [$($names)* $name] [$($bindings)*] [$name] [] $($pattern)+ ; $($tail)* }
+    };
+    ([$($args:tt)*] [$($names:tt)*] [$($bindings:tt)*] [$($field:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_fields_tuple! { [$($args)*] [$($names)*] [$($bindings)*] [$($field)* $next] $($tail)* }
+    };
+    ([$($args:tt)*] [$($names:tt)*] [$($bindings:tt)*] [$($field:tt)+]) => {
+        $crate::project_fields_tuple! { [$($args)*] [$($names)*] [$($bindings)*] [$($field)+] , }
+    };
+    ([$($args:tt)*] [$($names:tt)*] [$($bindings:tt)*] []) => {
+        // every field must be mentioned only once, otherwise projections would alias
+        const _: fn() = $crate::assert_fields_distinct::<
+            $crate::SameFieldProjectedTwice<{ !$crate::all_distinct(&[$(stringify!($names)),*]) }>,
+        >;
+        ($($bindings,)*)
+    };
+    // binding at the end of the pattern is replaced with `field`, e.g. `*inner` becomes `*field`
+    (pattern [$($args:tt)*] [$($names:tt)*] [$($bindings:tt)*] [$name:tt] [$($prefix:tt)*] $binding:ident ; $($tail:tt)*) => {
+        $crate::project_field_inner! { [$($args)*] { $name } : $($prefix)* field }
+        $crate::project_fields_tuple! { [$($args)*] [$($names)*] [$($bindings)* field] [] $($tail)* }
+    };
+    (pattern [$($args:tt)*] [$($names:tt)*] [$($bindings:tt)*] [$name:tt] [$($prefix:tt)*] $next:tt $($pattern:tt)+) => {
+        $crate::project_fields_tuple! { pattern [$($args)*] [$($names)*] [$($bindings)*] [$name] [$($prefix)* $next] $($pattern)+ }
     };
-    ([$ptr:ident $marker:ident $struct:ident] [] ) => {};
-    ([$ptr:ident $marker:ident $struct:ident] [] ..) => {};
-    ([$ptr:ident $marker:ident $struct:ident] [$($pattern:tt)*] $next:tt $($tail:tt)* ) => {
-        $crate::project_struct_fields! { [$ptr $marker $struct] [$($pattern)* $next] $($tail)*  }
+    (pattern [$($args:tt)*] [$($names:tt)*] [$($bindings:tt)*] [$name:tt] [$($prefix:tt)*] $($pattern:tt)*) => {
+        compile_error!("`project!(x -> { .. })` supports only field patterns that end with a binding, like `field: *name`");
     };
-    ([$ptr:ident $marker:ident $struct:ident] [$name:tt $($pattern:tt)*] ) => {
-        $crate::project_field_inner! { [$ptr $marker $struct] { $name } $($pattern)* }
+}
+
+// drops fields that were not mentioned in the pattern, if the projection is an owned one
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_drop_rest {
+    (tuple [$($args:tt)*] [$($moved:tt)*] $($moved_end:tt)*) => {
+        $crate::with_tuple_indexes! { project_drop_rest [from_end [$($args)*] [$((stringify!($moved)))*]] $($moved_end)* }
+    };
+    // fields after `..` are named by their negative index, `-1` is the last field
+    (from_end [$($args:tt)*] [$($moved:tt)*] [$zero:tt $($indexes:tt)*] [$($after:tt)*] $($tail:tt)*) => {
+        $crate::project_drop_rest! {
+            from_end_index [$($args)*] [$($moved)*] [$zero $($indexes)*] [$($after)*] [$($indexes)*] $($tail)*
+        }
+    };
+    (from_end_index [$($args:tt)*] [$($moved:tt)*] [$($all:tt)*] [$skip:tt $($after:tt)*] [$next:tt $($indexes:tt)*] $($tail:tt)*) => {
+        $crate::project_drop_rest! { from_end_index [$($args)*] [$($moved)*] [$($all)*] [$($after)*] [$($indexes)*] $($tail)* }
+    };
+    (from_end_index [$($args:tt)*] [$($moved:tt)*] [$($all:tt)*] [] [$next:tt $($indexes:tt)*] $($tail:tt)*) => {
+        $crate::project_drop_rest! { from_end [$($args)*] [$($moved)* (concat!("-", stringify!($next)))] [$($all)*] $($tail)* }
+    };
+    // too far from the end to be named, but such struct is too big for `Fields` derive anyway
+    (from_end_index [$($args:tt)*] [$($moved:tt)*] [$($all:tt)*] [$($after:tt)*] [] $($tail:tt)*) => {
+        $crate::project_drop_rest! { from_end [$($args)*] [$($moved)*] [$($all)*] $($tail)* }
+    };
+    (from_end [$($args:tt)*] [$($moved:tt)*] [$($all:tt)*]) => {
+        $crate::project_drop_rest! { [$($args)*] [$($moved)*] }
+    };
+    ([$ptr:ident $marker:ident $($rest:tt)*] [$($moved:tt)*]) => {
+        unsafe {
+            use $crate::DropRemainingFields;
+            enum Moved {}
+            impl $crate::MovedFields for Moved {
+                const MOVED: &'static [&'static str] = &[$($moved),*];
+            }
+            (&&($ptr, &$marker)).drop_remaining::<Moved>();
+        }
     };
 }
 
@@ -1083,6 +3021,79 @@ macro_rules! project_struct_fields {
 /// ```
 ///
 /// ```rust,compile_fail
+/// use projecture::project;
+/// use core::cell::Cell;
+/// enum Foo {
+///     A(usize),
+///     B(u8),
+/// }
+/// let tmp = Cell::new(Foo::A(1));
+/// project!(match &tmp {
+///     Foo::A(x) => x.set(2),
+///     Foo::B(x) => x.set(2),
+/// });
+/// ```
+///
+/// ```rust,compile_fail
+/// use projecture::project;
+/// enum Foo {
+///     A(usize),
+///     B(u8),
+/// }
+/// let tmp = Foo::A(1);
+/// let ptr = &tmp as *const Foo;
+/// project!(match ptr {
+///     Foo::A(x) => {}
+///     _ => {}
+/// });
+/// ```
+///
+/// ```rust,compile_fail
+/// use projecture::project;
+/// struct Foo {
+///     x: usize,
+///     y: usize,
+/// }
+/// let mut foo = Foo { x: 1, y: 2 };
+/// project!(let Foo { x, x: y } = &mut foo);
+/// ```
+///
+/// ```rust,compile_fail
+/// use projecture::project;
+/// struct Foo(usize, usize);
+/// let mut foo = Foo(1, 2);
+/// let (x, y) = project!((&mut foo) -> { 0, 0: y });
+/// ```
+///
+/// ```rust,compile_fail
+/// use projecture::project;
+/// let array = [1, 2, 3];
+/// project!(let [a, b] = &array);
+/// ```
+///
+/// ```rust,compile_fail
+/// use projecture::project;
+/// #[repr(packed)]
+/// struct Test(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, usize);
+/// let test = Test(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13);
+/// let _ = project!((&test) -> 13);
+/// ```
+///
+/// ```rust,compile_fail
+/// use projecture::project;
+/// struct Foo(usize, usize);
+/// let mut foo = Foo(1, 2);
+/// project!(let whole @ Foo(x, ..) = &mut foo);
+/// ```
+///
+/// ```rust,compile_fail
+/// use projecture::project;
+/// struct Foo(usize, usize);
+/// let foo = Foo(1, 2);
+/// project!(let Foo(1, x) = &foo);
+/// ```
+///
+/// ```rust,compile_fail
 /// use std::marker::PhantomPinned;
 /// use std::pin::Pin;
 /// use projecture::project;
@@ -1097,86 +3108,593 @@ macro_rules! project_struct_fields {
 #[doc(hidden)]
 #[macro_export]
 macro_rules! not_packed {
-    ($name:ident $field:ident) => {
+    ($name:ident $($field:ident)+) => {
         struct $name {
-            $field: (),
+            $($field: (),)+
         }
     };
+    (tuple [$($index:tt)*]) => {
+        #[doc(hidden)]
+        pub struct NotPackedTuple($(pub [(); $index]),*);
+    };
     ($name:ident $($field:tt)* ) => {
-        struct $name((), (), (), (), (), (), (), (), (), (), ());
+        type $name = $crate::NotPackedTuple;
     };
 }
 
+with_tuple_indexes! { not_packed [tuple] }
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! project_field_inner {
-    ( [$($args:tt)*] { } : $($pattern:tt)* ) => {
-        $crate::project_field_inner! { [$($args)*] { 0 } : $($pattern)* }
+    // pointer to the variant field was already found by `project_variant_fields!`
+    ( [$ptr:tt $marker:ident variant] $field:tt : [$field_ptr:ident] $($pattern:tt)* ) => {
+        #[allow(unused_mut)]
+        let (mut tmp, guard) = unsafe {
+            use $crate::{ProjectableMarker,Finalizer};
+            fn kind_of<U>(_: *mut U) -> core::marker::PhantomData<U> {
+                core::marker::PhantomData
+            }
+            // enums can't use `PinFields!`, so field type is the kind like for other structs
+            let marker = { use $crate::pin::SelectPinField; (&&(kind_of($field_ptr), &$marker)).select_marker() };
+            let tmp = core::mem::ManuallyDrop::new(marker.from_raw($field_ptr));
+            let result = (&&&&& tmp).call_finalize();
+            // pinned fields of `replace` are dropped when the projection is finished or on unwind
+            let guard = { use $crate::pin::SelectFieldGuard; (&&($field_ptr, marker)).field_guard() };
+            (result, guard)
+        };
+        unsafe { guard.link() };
+        $crate::project_nested!{ [$($pattern)*] tmp }
     };
-    ( [$($args:tt)*] { ! } : $($pattern:tt)* ) => {
-        $crate::project_field_inner! { [$($args)*] { 1 } : $($pattern)* }
+    ( [$ptr:tt $marker:ident [$($type:tt)+]] { + $($before:tt)* } : $($pattern:tt)* ) => {
+        $crate::project_field_inner! {
+            [$ptr $marker [$($type)+]] ( [field] $($type)+ ( $($crate::project_ignore!($before),)* ref field, .. ) ) : $($pattern)*
+        }
     };
-    ( [$($args:tt)*] { !! } : $($pattern:tt)* ) => {
-        $crate::project_field_inner! { [$($args)*] { 2 } : $($pattern)* }
+    ( [$ptr:tt $marker:ident [$($type:tt)+]] { - $($after:tt)* } : $($pattern:tt)* ) => {
+        $crate::project_field_inner! {
+            [$ptr $marker [$($type)+]] ( [field] $($type)+ ( .., ref field $(, $crate::project_ignore!($after))* ) ) : $($pattern)*
+        }
     };
-    ( [$($args:tt)*] { !!! } : $($pattern:tt)* ) => {
-        $crate::project_field_inner! { [$($args)*] { 3 } : $($pattern)* }
+    ( [$ptr:tt $marker:ident [$($type:tt)+]] ( [$field:ident] $($field_pattern:tt)* ) : $($pattern:tt)* ) => {
+        #[allow(unused_mut)]
+        let (mut tmp, guard) = unsafe {
+            use $crate::{ProjectableMarker,Finalizer};
+            fn to_ptr<U>(field: &U) -> *mut U {
+                field as *const U as *mut U
+            }
+            // field index is not known here, so pattern is used to find the field
+            // (which also fails to compile for `#[repr(packed)]` structs),
+            // and it can't be matched against the projected memory without creating references to it
+            let field_ptr = $crate::uninit_field($ptr, |base| match *base {
+                $($field_pattern)* => to_ptr($field),
+            });
+            let kind = { use $crate::pin::LookupPinFields; (&&$ptr).positional_pin_field() };
+            let marker = { use $crate::pin::SelectPinField; (&&(kind, &$marker)).select_marker() };
+            let tmp = core::mem::ManuallyDrop::new(marker.from_raw(field_ptr));
+            let result = (&&&&& tmp).call_finalize();
+            // pinned fields of `replace` are dropped when the projection is finished or on unwind
+            let guard = { use $crate::pin::SelectFieldGuard; (&&(field_ptr, marker)).field_guard() };
+            (result, guard)
+        };
+        unsafe { guard.link() };
+        $crate::project!(let $($pattern)* = tmp);
     };
-    ( [$($args:tt)*] { !!!! } : $($pattern:tt)* ) => {
-        $crate::project_field_inner! { [$($args)*] { 4 } : $($pattern)* }
+    // fields of the struct pattern are checked all at once by `project_struct_fields!` and `project_tuple_fields!`
+    ( [$ptr:tt $marker:ident [$($type:tt)+]] { $field:tt $(in $offsets:ident)? } $($pattern:tt)* ) => {
+        $crate::project_field_inner! { [$ptr $marker] { $field $(in $offsets)? } $($pattern)* }
     };
-    ( [$($args:tt)*] { !!!!! } : $($pattern:tt)* ) => {
-        $crate::project_field_inner! { [$($args)*] { 5 } : $($pattern)* }
+    ( [$ptr:tt $marker:ident] { $field:tt $(in $offsets:ident)? } : $($pattern:tt)* ) => {
+        #[allow(unused_mut)]
+        let (mut tmp, guard) = unsafe {
+            use $crate::{ProjectableMarker,Finalizer};
+            $crate::project_field_inner!(packed $ptr $marker [$field] $($offsets)?);
+            // null here means that there is nothing to project, e.g. `None` in `Option` projection
+            let field_ptr = if { use $crate::MayDangle; (&&(&$marker,)).may_dangle() } {
+                $crate::project_field_inner!(dangling $ptr $field $($offsets)?)
+            } else if $ptr.is_null() {
+                core::ptr::null_mut()
+            } else {
+                core::ptr::addr_of_mut!((*$ptr). $field)
+            };
+            // fields of `PinFields!` structs are pinned or not depending on their kind in the map
+            // (only the type is needed, so the field of the map is never projected)
+            let mut kind = core::marker::PhantomData;
+            if false {
+                use $crate::pin::LookupPinFields;
+                kind = $crate::pin::pin_field_kind(core::ptr::addr_of_mut!((*(&&$ptr).pin_fields_map()). $field));
+            }
+            let marker = { use $crate::pin::SelectPinField; (&&(kind, &$marker)).select_marker() };
+            let tmp = core::mem::ManuallyDrop::new(marker.from_raw(field_ptr));
+            let result = (&&&&& tmp).call_finalize();
+            // pinned fields of `replace` are dropped when the projection is finished or on unwind
+            let guard = { use $crate::pin::SelectFieldGuard; (&&(field_ptr, marker)).field_guard() };
+            (result, guard)
+        };
+        unsafe { guard.link() };
+        $crate::project!(let $($pattern)* = tmp);
+
     };
-    ( [$($args:tt)*] { !!!!!! } : $($pattern:tt)* ) => {
-        $crate::project_field_inner! { [$($args)*] { 6 } : $($pattern)* }
+    ( [$ptr:tt $marker:ident] { $field:ident $(in $offsets:ident)? } ) => {
+        $crate::project_field_inner! { [$ptr $marker] { $field $(in $offsets)? } : $field }
     };
-    ( [$($args:tt)*] { !!!!!!! } : $($pattern:tt)* ) => {
-        $crate::project_field_inner! { [$($args)*] { 7 } : $($pattern)* }
+    // fields of struct patterns with `$offsets` were already checked by `project_struct_fields!`
+    (packed $ptr:tt $marker:ident $fields:tt $offsets:ident) => {};
+    (packed $ptr:tt $marker:ident [$($field:tt)+]) => {
+        // check for #[packed] struct
+        #[forbid(unaligned_references)]
+        #[allow(dead_code)]
+        if false {
+            use $crate::SupportsPacked;
+            $crate::not_packed! { Foo $($field)+ }
+            let check_ptr = ( &&($ptr, &$marker, core::marker::PhantomData::<Foo>) ).select();
+            $( let _ = &(*check_ptr). $field; )+
+        }
     };
-    ( [$($args:tt)*] { !!!!!!!! } : $($pattern:tt)* ) => {
-        $crate::project_field_inner! { [$($args)*] { 8 } : $($pattern)* }
+    (dangling $ptr:ident $field:tt $offsets:ident) => {
+        $crate::next_field($ptr, &mut $offsets)
     };
-    ( [$($args:tt)*] { !!!!!!!!! } : $($pattern:tt)* ) => {
-        $crate::project_field_inner! { [$($args)*] { 9 } : $($pattern)* }
+    (dangling $ptr:ident $field:tt) => {
+        $crate::uninit_field($ptr, |base| core::ptr::addr_of_mut!((*base). $field))
     };
-    ( [$($args:tt)*] { !!!!!!!!!! } : $($pattern:tt)* ) => {
-        $crate::project_field_inner! { [$($args)*] { 10 } : $($pattern)* }
+}
+
+/// Offset in bytes of a field, or of a nested field like `field.subfield`, from the start of the struct.
+///
+/// Like field projection it never goes through `Deref`, so a field chain that would need an implicit deref
+/// fails to compile. Works with `#[repr(packed)]` structs.
+/// On Rust 1.65+ (where [`pointer::offset_from`](https://doc.rust-lang.org/std/primitive.pointer.html#method.offset_from)
+/// is `const`) it can also be used in `const` items.
+/// ```rust
+/// use projecture::offset_of;
+/// #[repr(C)]
+/// struct Foo {
+///     a: u8,
+///     b: Bar,
+/// }
+/// #[repr(C)]
+/// struct Bar(u16, u32);
+/// assert_eq!(offset_of!(Foo, a), 0);
+/// assert_eq!(offset_of!(Foo, b), 4);
+/// assert_eq!(offset_of!(Foo, b.1), 8);
+/// ```
+/// ```rust
+/// # use projecture::offset_of;
+/// # #[repr(C)]
+/// # struct Foo {
+/// #     a: u8,
+/// #     b: Bar,
+/// # }
+/// # #[repr(C)]
+/// # struct Bar(u16, u32);
+/// #[rustversion::since(1.65)]
+/// const OFFSET: usize = offset_of!(Foo, b.1);
+/// # #[rustversion::since(1.65)]
+/// # fn main() {
+/// assert_eq!(OFFSET, 8);
+/// # }
+/// # #[rustversion::before(1.65)]
+/// # fn main() {}
+/// ```
+/// ```rust,compile_fail
+/// # use projecture::offset_of;
+/// struct Foo {
+///     a: Box<(u8, u32)>,
+/// }
+/// let offset = offset_of!(Foo, a.1);
+/// ```
+#[macro_export]
+macro_rules! offset_of {
+    ($type:ty, $($field:tt).+ $(,)?) => {{
+        #[allow(unused_unsafe)]
+        let _ = |ptr: *mut $type| unsafe { $crate::offset_of_check!(ptr $($field).+); };
+        let uninit = core::mem::MaybeUninit::<$type>::uninit();
+        let base = uninit.as_ptr();
+        #[allow(unused_unsafe)]
+        let offset = unsafe {
+            (core::ptr::addr_of!((*base).$($field).+) as *const u8).offset_from(base as *const u8)
+        };
+        offset as usize
+    }};
+}
+
+// checked one field at a time to detect deref in the middle of the chain
+#[doc(hidden)]
+#[macro_export]
+macro_rules! offset_of_check {
+    ($ptr:ident $field:tt $(. $rest:tt)*) => {
+        let ptr = {
+            use $crate::CheckNoDeref;
+            (&&$ptr).check_deref()
+        };
+        let ptr = core::ptr::addr_of_mut!((*ptr).$field);
+        $crate::offset_of_check!(ptr $($rest).*)
     };
-    ( [$ptr:tt $marker:ident $type:ident] { $field:tt } $($pattern:tt)* ) => {
-        if false {
-            let $type { $field : _ , .. } = unsafe { &*$ptr };
-        }
-        $crate::project_field_inner! { [$ptr $marker] { $field } $($pattern)* }
+    ($ptr:ident) => {};
+}
+
+/// Derive macro for [`macro_rules_attribute`](https://docs.rs/macro_rules_attribute)
+/// that implements [`DropFields`], so that owned destructuring with `..` drops unmentioned fields instead of leaking them.
+/// ```rust
+/// # use std::rc::Rc;
+/// use projecture::{project, Fields};
+/// use macro_rules_attribute::derive;
+/// #[derive(Fields!)]
+/// struct Foo {
+///     x: Rc<()>,
+///     y: Rc<()>,
+/// }
+/// impl Drop for Foo {
+///     fn drop(&mut self) {
+///         unreachable!()
+///     }
+/// }
+/// let rc = Rc::new(());
+/// let foo = Foo { x: rc.clone(), y: rc.clone() };
+/// project!(let Foo { x, .. } = foo);
+/// assert_eq!(Rc::strong_count(&rc), 2);
+/// drop(x);
+/// assert_eq!(Rc::strong_count(&rc), 1);
+/// ```
+#[macro_export]
+#[allow(non_snake_case)]
+macro_rules! Fields {
+    ( [struct $($head:tt)*] { $($inner:tt)* } ) => {
+        $crate::parse_generics!{ [drop_fields_impl named { $($inner)* }] $($head)* }
     };
-    ( [$ptr:tt $marker:ident] { $field:tt } : $($pattern:tt)* ) => {
-        #[allow(unused_mut)]
-        let mut tmp = unsafe {
-            use $crate::{ProjectableMarker,Finalizer,SupportsPacked};
-            // check for #[packed] struct
-            #[forbid(unaligned_references)]
-            #[allow(dead_code)]
-            if false{
-                $crate::not_packed! { Foo $field }
-                let check_ptr = ( &&($ptr, &$marker, core::marker::PhantomData::<Foo>) ).select();
-                let _ = &(*check_ptr). $field;
-            }
-            fn create_uninit<T>(_ptr: *mut T) -> core::mem::MaybeUninit<T>{ core::mem::MaybeUninit::uninit() }
-            let mut mu = create_uninit($ptr);
-            let mu_ptr = mu.as_mut_ptr();
-            let mu_field_ptr = core::ptr::addr_of_mut!((*mu_ptr). $field );
-            let offset = (mu_field_ptr as *mut u8).offset_from(mu_ptr as *mut u8);
-            fn do_offset<T,U>(ptr:*mut T, _field_ptr_type: *mut U, offset: isize) -> *mut U{
-                (ptr as *mut u8).wrapping_offset(offset) as *mut U
+    ( [struct $($head:tt)*] ($($inner:tt)*) ; ) => {
+        $crate::parse_generics!{ [drop_fields_impl tuple ( $($inner)* )] $($head)* }
+    };
+    // where clause of a tuple struct
+    ( [struct $($head:tt)*] ($($inner:tt)*) [$($where:tt)*] ; ) => {
+        $crate::parse_generics!{ [drop_fields_impl tuple ( $($inner)* )] $($head)* $($where)* }
+    };
+    ( [struct $($head:tt)*] ($($inner:tt)*) [$($where:tt)*] $token:tt $($tail:tt)+ ) => {
+        $crate::Fields!{ [struct $($head)*] ($($inner)*) [$($where)* $token] $($tail)+ }
+    };
+    ( [struct $($head:tt)*] ($($inner:tt)*) where $($tail:tt)+ ) => {
+        $crate::Fields!{ [struct $($head)*] ($($inner)*) [where] $($tail)+ }
+    };
+    ( [struct $($head:tt)*] ; ) => {
+        $crate::parse_generics!{ [drop_fields_impl named {}] $($head)* }
+    };
+    ( [enum   $($head:tt)*] { $($inner:tt)* } ) => {
+        $crate::parse_generics!{ [drop_fields_impl enum { $($inner)* }] $($head)* }
+    };
+    ( [$($head:tt)*] $token:tt $($tail:tt)* ) => { $crate::Fields!{ [$($head)* $token] $($tail)* } };
+    ( $(#[$attr:meta])* $vis:vis struct $($tail:tt)* ) => { $crate::Fields!{ [struct] $($tail)* } };
+    ( $(#[$attr:meta])* $vis:vis enum $($tail:tt)* ) => { $crate::Fields!{ [enum] $($tail)* } };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! drop_fields_impl {
+    ([$kind:ident $fields:tt] [$($generics:tt)*] [$($type:tt)+] [$($where:tt)*]) => {
+        unsafe impl<$($generics)*> $crate::DropFields for $($type)+ where $($where)* {
+            #[allow(unused_variables, irrefutable_let_patterns)]
+            unsafe fn drop_fields_except<__Moved: $crate::MovedFields>(this: *mut Self) {
+                $crate::drop_fields_impl! { $kind [this] $fields }
             }
-            let field_ptr = do_offset($ptr, mu_field_ptr, offset);
-            let tmp = core::mem::ManuallyDrop::new($marker.from_raw(field_ptr));
-            (&&&&& tmp).call_finalize()
-        };
-        $crate::project!(let $($pattern)* = tmp);
+        }
+    };
+    // whether the field was moved is an associated constant, so guards of moved fields get a null pointer.
+    // Guards are collected in a tuple, which keeps dropping the other fields if one of them panics
+    (drop [$($is_moved:tt)+] $field:expr) => {{
+        trait IsMoved {
+            const FIELD: bool;
+        }
+        impl<__Moved: $crate::MovedFields> IsMoved for __Moved {
+            const FIELD: bool = $($is_moved)+;
+        }
+        $crate::DropFieldGuard::new(if <__Moved as IsMoved>::FIELD { core::ptr::null_mut() } else { $field })
+    }};
+    (named [$this:ident] { $($(#[$attr:meta])* $vis:vis $field:ident : $type:ty),* $(,)? }) => {
+        drop(($(
+            $crate::drop_fields_impl! {
+                drop [$crate::is_field_moved(__Moved::MOVED, stringify!($field))] core::ptr::addr_of_mut!((*$this).$field)
+            },
+        )*));
+    };
+    (tuple [$this:ident] ( $($(#[$attr:meta])* $vis:vis $type:ty),* $(,)? )) => {
+        const COUNT: usize = [$(stringify!($type)),*].len();
+        drop($crate::with_tuple_indexes! { drop_fields_impl [tuple [$this] [$($type),*]] });
+    };
+    // nested tuples drop their first element before the rest, so fields are still dropped in order
+    (tuple [$this:ident] [$type:ty $(, $rest:ty)*] [$idx:tt $($indexes:tt)*]) => {(
+        $crate::drop_fields_impl! {
+            drop [$crate::is_tuple_field_moved(__Moved::MOVED, $idx, COUNT)] core::ptr::addr_of_mut!((*$this).$idx)
+        },
+        $crate::drop_fields_impl! { tuple [$this] [$($rest),*] [$($indexes)*] },
+    )};
+    (tuple [$this:ident] [] [$($indexes:tt)*]) => { () };
+    (tuple [$this:ident] [$($rest:ty),*] []) => {
+        compile_error!("`Fields` supports only tuple structs with up to 256 fields");
+    };
+    (enum [$this:ident] {
+        $($(#[$attr:meta])* $variant:ident $({ $($named:tt)* })? $(( $($tuple:tt)* ))? $(= $discriminant:expr)?),* $(,)?
+    }) => {
+        $(
+            $crate::drop_fields_impl! { variant [$this $variant] $({ $($named)* })? $(( $($tuple)* ))? }
+        )*
+    };
+    (variant [$this:ident $variant:ident] { $($(#[$attr:meta])* $field:ident : $type:ty),* $(,)? }) => {
+        // references to all fields are taken at once while the value is still fully valid
+        if let Self::$variant { $($field),* } = &mut *$this {
+            drop(($(
+                $crate::drop_fields_impl! { drop [$crate::is_field_moved(__Moved::MOVED, stringify!($field))] $field },
+            )*));
+        }
+    };
+    (variant [$this:ident $variant:ident] ( $($(#[$attr:meta])* $type:ty),* $(,)? )) => {
+        $crate::with_tuple_indexes! { drop_fields_impl [variant [$this $variant] [] [$($type),*]] }
+    };
+    // each `field` ident comes from a different expansion so they don't clash because of hygiene
+    (variant [$this:ident $variant:ident] [$($bound:tt)*] [$type:ty $(, $rest:ty)*] [$idx:tt $($indexes:tt)*]) => {
+        $crate::drop_fields_impl! {
+            variant [$this $variant] [$($bound)* ($idx field)] [$($rest),*] [$($indexes)*]
+        }
+    };
+    (variant [$this:ident $variant:ident] [$(($idx:tt $field:ident))*] [] [$($indexes:tt)*]) => {
+        if let Self::$variant { $($idx: $field),* } = &mut *$this {
+            const COUNT: usize = [$(stringify!($idx)),*].len();
+            drop(($(
+                $crate::drop_fields_impl! { drop [$crate::is_tuple_field_moved(__Moved::MOVED, $idx, COUNT)] $field },
+            )*));
+        }
+    };
+    (variant [$this:ident $variant:ident] [$($bound:tt)*] [$($rest:ty),*] []) => {
+        compile_error!("`Fields` supports only tuple variants with up to 256 fields");
+    };
+    (variant [$this:ident $variant:ident]) => {};
+}
+
+// Parses generics of the type in `Type<generics> where bounds` form and passes
+// `[generics] [type with generic parameters] [where bounds]` into the `$callback` macro.
+// Generics keep bounds but not defaults, which are not allowed in `impl`,
+// and type gets only parameter names. `[$($depth)*]` has a `<` for each unclosed angle bracket inside a parameter.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! parse_generics {
+    (name $cb:tt [$($type:tt)+] < $($tail:tt)*) => {
+        $crate::parse_generics!{ param $cb [] [$($type)+ <] $($tail)* }
+    };
+    (name $cb:tt [$($type:tt)+] where $($tail:tt)*) => {
+        $crate::parse_generics!{ done $cb [] [$($type)+] [$($tail)*] }
+    };
+    (name $cb:tt [$($type:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::parse_generics!{ name $cb [$($type)* $next] $($tail)* }
+    };
+    (name $cb:tt [$($type:tt)+]) => {
+        $crate::parse_generics!{ done $cb [] [$($type)+] [] }
+    };
+
+    // start of the generic parameter
+    (param $cb:tt [$($generics:tt)*] [$($type:tt)*] > $($tail:tt)*) => {
+        $crate::parse_generics!{ after $cb [$($generics)*] [$($type)* >] $($tail)* }
+    };
+    (param $cb:tt [$($generics:tt)*] [$($type:tt)*] const $name:ident $($tail:tt)*) => {
+        $crate::parse_generics!{ bound $cb [] [$($generics)* const $name] [$($type)* $name] $($tail)* }
+    };
+    (param $cb:tt [$($generics:tt)*] [$($type:tt)*] $name:lifetime $($tail:tt)*) => {
+        $crate::parse_generics!{ bound $cb [] [$($generics)* $name] [$($type)* $name] $($tail)* }
+    };
+    (param $cb:tt [$($generics:tt)*] [$($type:tt)*] $name:ident $($tail:tt)*) => {
+        $crate::parse_generics!{ bound $cb [] [$($generics)* $name] [$($type)* $name] $($tail)* }
+    };
+
+    // bounds of the parameter are kept
+    (bound $cb:tt [] $generics:tt $type:tt = $($tail:tt)*) => {
+        $crate::parse_generics!{ default $cb [] $generics $type $($tail)* }
+    };
+    (bound $cb:tt [] [$($generics:tt)*] [$($type:tt)*] , $($tail:tt)*) => {
+        $crate::parse_generics!{ param $cb [$($generics)* ,] [$($type)* ,] $($tail)* }
+    };
+    (bound $cb:tt [] [$($generics:tt)*] [$($type:tt)*] > $($tail:tt)*) => {
+        $crate::parse_generics!{ after $cb [$($generics)*] [$($type)* >] $($tail)* }
+    };
+    (bound $cb:tt [$($depth:tt)*] [$($generics:tt)*] $type:tt < $($tail:tt)*) => {
+        $crate::parse_generics!{ bound $cb [$($depth)* <] [$($generics)* <] $type $($tail)* }
+    };
+    (bound $cb:tt [$($depth:tt)*] [$($generics:tt)*] $type:tt << $($tail:tt)*) => {
+        $crate::parse_generics!{ bound $cb [$($depth)* < <] [$($generics)* <<] $type $($tail)* }
+    };
+    (bound $cb:tt [< $($depth:tt)*] [$($generics:tt)*] $type:tt > $($tail:tt)*) => {
+        $crate::parse_generics!{ bound $cb [$($depth)*] [$($generics)* >] $type $($tail)* }
+    };
+    (bound $cb:tt [< $($depth:tt)*] [$($generics:tt)*] $type:tt >> $($tail:tt)*) => {
+        $crate::parse_generics!{ bound $cb [$($depth)*] [$($generics)* >] $type > $($tail)* }
+    };
+    (bound $cb:tt $depth:tt [$($generics:tt)*] $type:tt $next:tt $($tail:tt)*) => {
+        $crate::parse_generics!{ bound $cb $depth [$($generics)* $next] $type $($tail)* }
+    };
+
+    // default of the parameter is skipped
+    (default $cb:tt [] $generics:tt $type:tt , $($tail:tt)*) => {
+        $crate::parse_generics!{ bound $cb [] $generics $type , $($tail)* }
+    };
+    (default $cb:tt [] $generics:tt $type:tt > $($tail:tt)*) => {
+        $crate::parse_generics!{ bound $cb [] $generics $type > $($tail)* }
+    };
+    (default $cb:tt [$($depth:tt)*] $generics:tt $type:tt < $($tail:tt)*) => {
+        $crate::parse_generics!{ default $cb [$($depth)* <] $generics $type $($tail)* }
+    };
+    (default $cb:tt [$($depth:tt)*] $generics:tt $type:tt << $($tail:tt)*) => {
+        $crate::parse_generics!{ default $cb [$($depth)* < <] $generics $type $($tail)* }
+    };
+    (default $cb:tt [< $($depth:tt)*] $generics:tt $type:tt > $($tail:tt)*) => {
+        $crate::parse_generics!{ default $cb [$($depth)*] $generics $type $($tail)* }
+    };
+    (default $cb:tt [< $($depth:tt)*] $generics:tt $type:tt >> $($tail:tt)*) => {
+        $crate::parse_generics!{ default $cb [$($depth)*] $generics $type > $($tail)* }
+    };
+    (default $cb:tt $depth:tt $generics:tt $type:tt $next:tt $($tail:tt)*) => {
+        $crate::parse_generics!{ default $cb $depth $generics $type $($tail)* }
+    };
+
+    (after $cb:tt $generics:tt $type:tt where $($tail:tt)*) => {
+        $crate::parse_generics!{ done $cb $generics $type [$($tail)*] }
+    };
+    (after $cb:tt $generics:tt $type:tt) => {
+        $crate::parse_generics!{ done $cb $generics $type [] }
+    };
+
+    (done [$cb:ident $($args:tt)*] [$($generics:tt)*] [$($type:tt)+] [$($where:tt)*]) => {
+        $crate::$cb!{ [$($args)*] [$($generics)*] [$($type)+] [$($where)*] }
+    };
+
+    ([$($cb:tt)*] $($tail:tt)* ) => { $crate::parse_generics!{ name [$($cb)*] [] $($tail)* } };
+}
+
+// Converts a pattern into the one that is used to check whether the enum place matches.
+// Bindings and deref patterns are replaced with `_`, so only literals, ranges and variant paths are checked.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_check_pat {
+    (pat [_]) => { _ };
+    (pat [ref $($pat:tt)*]) => { _ };
+    (pat [mut $($pat:tt)*]) => { _ };
+    (pat [* $($pat:tt)*]) => { _ };
+    (pat [& mut $($pat:tt)*]) => { $crate::project_check_pat!{ pat [$($pat)*] } };
+    (pat [& $($pat:tt)*]) => { $crate::project_check_pat!{ pat [$($pat)*] } };
+    (pat [$name:ident @ $($pat:tt)*]) => { $crate::project_check_pat!{ pat [$($pat)*] } };
+    (pat [( $($fields:tt)* )]) => {
+        $crate::project_check_pat!{ tuple [] [] [] $($fields)* }
+    };
+    (pat [[ $($items:tt)* ]]) => {
+        $crate::project_check_pat!{ array [] [] $($items)* }
+    };
+    (pat [$($pat:tt)*]) => { $crate::project_check_pat!{ path [] $($pat)* } };
+
+    (path [$($path:tt)+] { $($fields:tt)* }) => {
+        $crate::project_check_pat!{ struct [$($path)+] [] [] $($fields)* }
+    };
+    (path [$($path:tt)+] ( $($fields:tt)* )) => {
+        $crate::project_check_pat!{ tuple [$($path)+] [] [] $($fields)* }
+    };
+    (path [$($path:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_check_pat!{ path [$($path)* $next] $($tail)* }
+    };
+    // literals, ranges, constants and plain bindings are used as is
+    (path [$($pat:tt)*]) => { $($pat)* };
+
+    (struct [$($path:tt)*] [$($checked:tt)*] [$($field:tt)*] , $($tail:tt)*) => {
+        $crate::project_check_pat!{ struct_field [$($path)*] [$($checked)*] [$($field)*] $($tail)* }
+    };
+    (struct [$($path:tt)*] [$($checked:tt)*] [$($field:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_check_pat!{ struct [$($path)*] [$($checked)*] [$($field)* $next] $($tail)* }
+    };
+    (struct [$($path:tt)*] [$($checked:tt)*] [$($field:tt)+]) => {
+        $crate::project_check_pat!{ struct_field [$($path)*] [$($checked)*] [$($field)+] }
+    };
+    (struct [$($path:tt)*] [$($checked:tt)*] []) => { $($path)* { $($checked)* } };
+    (struct_field [$($path:tt)*] [$($checked:tt)*] [..] $($tail:tt)*) => {
+        $crate::project_check_pat!{ struct [$($path)*] [$($checked)* ..] [] $($tail)* }
+    };
+    (struct_field [$($path:tt)*] [$($checked:tt)*] [$name:tt : $($pat:tt)+] $($tail:tt)*) => {
+        $crate::project_check_pat!{ struct [$($path)*] [$($checked)* $name: $crate::project_check_pat!{ pat [$($pat)+] },] [] $($tail)* }
+    };
+    // shorthand binding
+    (struct_field [$($path:tt)*] [$($checked:tt)*] [$name:ident] $($tail:tt)*) => {
+        $crate::project_check_pat!{ struct [$($path)*] [$($checked)* $name: _,] [] $($tail)* }
+    };
+    (struct_field [$($path:tt)*] [$($checked:tt)*] [$modifier:ident $($name:ident)+] $($tail:tt)*) => {
+        $crate::project_check_pat!{ struct_field [$($path)*] [$($checked)*] [$($name)+] $($tail)* }
+    };
+
+    (tuple [$($path:tt)*] [$($checked:tt)*] [..] $(, $($tail:tt)*)?) => {
+        $crate::project_check_pat!{ tuple [$($path)*] [$($checked)* ..,] [] $($($tail)*)? }
+    };
+    (tuple [$($path:tt)*] [$($checked:tt)*] [$($field:tt)*] , $($tail:tt)*) => {
+        $crate::project_check_pat!{ tuple [$($path)*] [$($checked)* $crate::project_check_pat!{ pat [$($field)*] },] [] $($tail)* }
+    };
+    (tuple [$($path:tt)*] [$($checked:tt)*] [$($field:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_check_pat!{ tuple [$($path)*] [$($checked)*] [$($field)* $next] $($tail)* }
+    };
+    (tuple [$($path:tt)*] [$($checked:tt)*] [$($field:tt)+]) => {
+        $($path)* ( $($checked)* $crate::project_check_pat!{ pat [$($field)+] } )
+    };
+    (tuple [$($path:tt)*] [$($checked:tt)*] []) => { $($path)* ( $($checked)* ) };
 
+    (array [$($checked:tt)*] [$($name:ident @)? ..] $(, $($tail:tt)*)?) => {
+        $crate::project_check_pat!{ array [$($checked)* ..,] [] $($($tail)*)? }
+    };
+    (array [$($checked:tt)*] [$($item:tt)*] , $($tail:tt)*) => {
+        $crate::project_check_pat!{ array [$($checked)* $crate::project_check_pat!{ pat [$($item)*] },] [] $($tail)* }
+    };
+    (array [$($checked:tt)*] [$($item:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::project_check_pat!{ array [$($checked)*] [$($item)* $next] $($tail)* }
+    };
+    (array [$($checked:tt)*] [$($item:tt)+]) => {
+        [ $($checked)* $crate::project_check_pat!{ pat [$($item)+] } ]
+    };
+    (array [$($checked:tt)*] []) => { [ $($checked)* ] };
+}
+
+// Projects a field pattern of the value that is already known to match, so it can be refutable.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_nested {
+    ([$lit:literal] $val:ident) => {
+        #[allow(clippy::drop_non_drop)]
+        drop($val);
+    };
+    ([- $lit:literal] $val:ident) => {
+        #[allow(clippy::drop_non_drop)]
+        drop($val);
+    };
+    ([$($start:tt)? ..= $($end:tt)+] $val:ident) => {
+        #[allow(clippy::drop_non_drop)]
+        drop($val);
+    };
+    ([- $start:tt ..= $($end:tt)+] $val:ident) => {
+        #[allow(clippy::drop_non_drop)]
+        drop($val);
+    };
+    ([$name:ident @ $($pat:tt)+] $val:ident) => {
+        let $name = $crate::Duplicate::duplicate(&$val);
+        $crate::project_nested! { [$($pat)+] $val }
+    };
+    ([mut $name:ident @ $($pat:tt)+] $val:ident) => {
+        let mut $name = $crate::Duplicate::duplicate(&$val);
+        $crate::project_nested! { [$($pat)+] $val }
+    };
+    ([& mut $($pat:tt)+] $val:ident) => {
+        let copied = *$val;
+        #[allow(clippy::drop_non_drop)]
+        drop($val);
+        $crate::project_nested! { [$($pat)+] copied }
+    };
+    ([& $($pat:tt)+] $val:ident) => {
+        let copied = *$val;
+        #[allow(clippy::drop_non_drop)]
+        drop($val);
+        $crate::project_nested! { [$($pat)+] copied }
+    };
+    // unit variant or constant
+    ([$first:ident $(:: $path:ident)+] $val:ident) => {
+        #[allow(clippy::drop_non_drop)]
+        drop($val);
+    };
+    ([$($path:ident)::+ { $($fields:tt)* }] $val:ident) => {
+        $crate::project!(let $($path)::+ { $($fields)* } = $val else { unreachable!() });
+    };
+    ([$($path:ident)::+ ( $($fields:tt)* )] $val:ident) => {
+        $crate::project!(let $($path)::+ ( $($fields)* ) = $val else { unreachable!() });
+    };
+    ([[ $($items:tt)* ]] $val:ident) => {
+        $crate::project_array_fields! { [nested] [$($items)*] $val }
+    };
+    ([$($pat:tt)*] $val:ident) => {
+        $crate::project!(let $($pat)* = $val);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! project_ignore {
+    ($($ignored:tt)*) => {
+        _
     };
-    ( [$ptr:tt $marker:ident] { $field:ident } ) => { $crate::project_field_inner! { [$ptr $marker] { $field } : $field } };
 }
 
 /// Keeps track of stuff that was left of `T` when we moved out `T::Target` from it.
@@ -1263,7 +3781,6 @@ impl<'a, T> ProjectableMarker<T> for OwningMarker<'a, T> {
 }
 
 //todo:
-// pattern matching/enums
 // foldable trait
 //
 
@@ -1,34 +1,14 @@
 use crate::{
-    CustomWrapper, DerefProjectable, FinalizeProjection, Helper, Marker, Owned, Projectable,
-    ProjectableMarker, SupportsPacked,
+    CustomWrapper, DerefChain, DerefProjectable, FinalizeProjection, Projectable,
+    ProjectableMarker, SupportsPacked, WrapMarker, WrappingIndex,
 };
 use core::marker::PhantomData;
-use core::mem::transmute_copy;
+use core::mem::{transmute_copy, ManuallyDrop};
 use core::ptr::null_mut;
 
-unsafe impl<T> CustomWrapper for Option<T> {
-    type Output = Option<Owned<T>>;
-}
-unsafe impl<'a, T> CustomWrapper for &Option<&'a T> {
-    type Output = Option<Helper<&'a T>>;
-}
-unsafe impl<'a, T> CustomWrapper for &Option<&'a mut T> {
-    type Output = Option<Helper<&'a mut T>>;
-}
-unsafe impl<T: CustomWrapper> CustomWrapper for &&Option<T> {
+unsafe impl<T: CustomWrapper> CustomWrapper for Option<T> {
     type Output = Option<T::Output>;
 }
-unsafe impl<T> Projectable for Option<Owned<T>> {
-    type Target = T;
-    type Marker = OptionMarker<<Owned<T> as Projectable>::Marker>;
-
-    fn get_raw(&self) -> (*mut Self::Target, Self::Marker) {
-        (
-            unsafe { core::mem::transmute(self.as_ref()) },
-            self.as_ref().map(|_| Marker::new()).into(),
-        )
-    }
-}
 
 // unsafe impl<'a, T> Projectable for &Option<T>
 // where
@@ -49,6 +29,7 @@ unsafe impl<T> Projectable for Option<Owned<T>> {
 #[repr(transparent)]
 pub struct OptionMarker<T>(Option<T>);
 impl<T> OptionMarker<T> {
+    #[deprecated(note = "`project!` no longer requires markers to have a `check` method")]
     pub fn check(&self) {}
 }
 impl<T> From<Option<T>> for OptionMarker<T> {
@@ -57,40 +38,47 @@ impl<T> From<Option<T>> for OptionMarker<T> {
     }
 }
 
-unsafe impl<T> Projectable for &&Option<T>
+/// Pointer wrapped in any number of `Option`s, nested options are flattened into a single one.
+/// `Option` itself is intentionally not `Projectable`, otherwise these impls would overlap.
+#[doc(hidden)]
+pub unsafe trait OptionProjectable {
+    type Target;
+    type Marker;
+    fn get_raw_option(&self) -> Option<(*mut Self::Target, Self::Marker)>;
+}
+unsafe impl<T: Projectable> OptionProjectable for T
 where
-    T: Projectable,
+    T::Target: Sized,
 {
     type Target = T::Target;
-    type Marker = OptionMarker<T::Marker>;
+    type Marker = T::Marker;
 
-    fn get_raw(&self) -> (*mut Self::Target, Self::Marker) {
-        if let Some(x) = self {
-            let (raw, marker) = x.get_raw();
-            (raw as _, Some(marker).into())
-        } else {
-            (null_mut(), None.into())
-        }
+    fn get_raw_option(&self) -> Option<(*mut Self::Target, Self::Marker)> {
+        Some(self.get_raw())
     }
 }
-unsafe impl<'a, T> Projectable for &&&'a Option<T>
-where
-    &'a T: Projectable,
-{
-    type Target = <&'a T as Projectable>::Target;
-    type Marker = OptionMarker<<&'a T as Projectable>::Marker>;
+unsafe impl<T: OptionProjectable> OptionProjectable for Option<T> {
+    type Target = T::Target;
+    type Marker = T::Marker;
+
+    fn get_raw_option(&self) -> Option<(*mut Self::Target, Self::Marker)> {
+        self.as_ref().and_then(|x| x.get_raw_option())
+    }
+}
+
+unsafe impl<T: OptionProjectable> Projectable for &&Option<T> {
+    type Target = T::Target;
+    type Marker = OptionMarker<T::Marker>;
 
     fn get_raw(&self) -> (*mut Self::Target, Self::Marker) {
-        if let Some(x) = self {
-            let (raw, marker) = x.get_raw();
-            (raw as _, Some(marker).into())
-        } else {
-            (null_mut(), None.into())
+        match (***self).get_raw_option() {
+            Some((raw, marker)) => (raw, Some(marker).into()),
+            None => (null_mut(), None.into()),
         }
     }
 }
 
-impl<T, M: ProjectableMarker<T>> ProjectableMarker<T> for OptionMarker<M> {
+impl<T: ?Sized, M: ProjectableMarker<T>> ProjectableMarker<T> for OptionMarker<M> {
     type Output = Option<M::Output>;
 
     unsafe fn from_raw(&self, raw: *mut T) -> Self::Output {
@@ -98,6 +86,14 @@ impl<T, M: ProjectableMarker<T>> ProjectableMarker<T> for OptionMarker<M> {
     }
 }
 
+unsafe impl<W, M: WrapMarker<W>> WrapMarker<W> for OptionMarker<M> {
+    type Output = OptionMarker<M::Output>;
+
+    fn wrap_marker(self) -> Self::Output {
+        OptionMarker(self.0.map(M::wrap_marker))
+    }
+}
+
 impl<T: FinalizeProjection> FinalizeProjection for Option<T> {
     type Output = Option<T::Output>;
 
@@ -105,6 +101,17 @@ impl<T: FinalizeProjection> FinalizeProjection for Option<T> {
         self.as_ref().map(|x| x.finalize())
     }
 }
+// for finalizers that are implemented on a reference to do autoderef specialization
+impl<'b, T> FinalizeProjection for &&'b Option<T>
+where
+    &'b T: FinalizeProjection,
+{
+    type Output = Option<<&'b T as FinalizeProjection>::Output>;
+
+    unsafe fn finalize(&self) -> Self::Output {
+        (**self).as_ref().map(|x| x.finalize())
+    }
+}
 
 // todo make more general Option flattening
 impl<T> FinalizeProjection for &Option<Option<T>> {
@@ -136,6 +143,23 @@ where
         }
     }
 }
+unsafe impl<P: DerefChain<M>, M> DerefChain<M> for Option<P>
+where
+    // same limitation as above
+    P::Target: Sized,
+{
+    type Target = P::Target;
+    type Marker = OptionMarker<P::Marker>;
+
+    unsafe fn deref_chain(this: *mut Self, outer: M) -> (*mut Self::Target, Self::Marker) {
+        if let Some(x) = &*this {
+            let (raw, marker) = P::deref_chain(x as *const P as *mut P, outer);
+            (raw, Some(marker).into())
+        } else {
+            (null_mut(), None.into())
+        }
+    }
+}
 unsafe impl<'a, T, Target, Marker> DerefProjectable for &'a Option<T>
 where
     &'a T: DerefProjectable<Target = Target, Marker = Marker>,
@@ -160,3 +184,27 @@ where
 {
     type Result = NotPacked;
 }
+
+unsafe impl<M: WrappingIndex> WrappingIndex for OptionMarker<M> {}
+
+/// Used by `?` in `->` chains to make sure that there is exactly one level of `Option`
+/// before the inner option is projected, so nested options are flattened along the way.
+#[doc(hidden)]
+pub trait OptionalChain {
+    type Output;
+    unsafe fn lift_option(&self) -> Self::Output;
+}
+impl<T> OptionalChain for ManuallyDrop<T> {
+    type Output = Option<T>;
+
+    unsafe fn lift_option(&self) -> Self::Output {
+        Some(transmute_copy(self))
+    }
+}
+impl<T> OptionalChain for &ManuallyDrop<Option<T>> {
+    type Output = Option<T>;
+
+    unsafe fn lift_option(&self) -> Self::Output {
+        transmute_copy(*self)
+    }
+}
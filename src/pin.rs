@@ -2,16 +2,7 @@ use super::*;
 use core::pin::Pin;
 
 // unsafe impl<T> MarkerNonOwned for Pin<T> {}
-unsafe impl<T> CustomWrapper for Pin<T> {
-    type Output = Pin<T>;
-}
-unsafe impl<'a, T> CustomWrapper for &Pin<&'a T> {
-    type Output = Pin<Helper<&'a T>>;
-}
-unsafe impl<'a, T> CustomWrapper for &Pin<&'a mut T> {
-    type Output = Pin<Helper<&'a mut T>>;
-}
-unsafe impl<T: CustomWrapper> CustomWrapper for &&Pin<T> {
+unsafe impl<T: CustomWrapper> CustomWrapper for Pin<T> {
     type Output = Pin<T::Output>;
 }
 
@@ -100,8 +91,8 @@ macro_rules! impl_pin {
 
 // impl_pin! {}
 // impl_pin! { mut }
-unsafe impl<'a, P: Deref<Target = T> + Projectable<Target = T>, T: PinProjectable> Projectable
-    for Pin<P>
+unsafe impl<'a, P: Deref<Target = T> + Projectable<Target = T>, T: ?Sized + PinProjectable>
+    Projectable for Pin<P>
 {
     type Target = T;
     type Marker = PinMarker<P::Marker>;
@@ -111,9 +102,34 @@ unsafe impl<'a, P: Deref<Target = T> + Projectable<Target = T>, T: PinProjectabl
         (res.0, PinMarker(res.1))
     }
 }
+// shared borrow of any pinned pointer, including the ones that can't be projected by value like `Pin<Rc<T>>`,
+// takes priority over projecting `Pin<P>` itself as a struct
+unsafe impl<'a, P: Deref<Target = T>, T: ?Sized + PinProjectable> Projectable
+    for &Helper<&'a Pin<P>>
+{
+    type Target = T;
+    type Marker = PinMarker<Marker<&'a ()>>;
+
+    fn get_raw(&self) -> (*mut Self::Target, Self::Marker) {
+        (
+            self.0.as_ref().get_ref() as *const _ as _,
+            PinMarker(Marker::new()),
+        )
+    }
+}
+#[repr(transparent)]
 pub struct PinMarker<T>(pub T);
+unsafe impl<T: SupportsEnums> SupportsEnums for PinMarker<T> {}
+
+unsafe impl<M: WrapMarker<MaybeUninit<()>>> WrapMarker<MaybeUninit<()>> for PinMarker<M> {
+    type Output = PinMarker<M::Output>;
+
+    fn wrap_marker(self) -> Self::Output {
+        PinMarker(self.0.wrap_marker())
+    }
+}
 
-impl<'a, T: 'a, P: ProjectableMarker<T>> ProjectableMarker<T> for PinMarker<P>
+impl<'a, T: ?Sized + 'a, P: ProjectableMarker<T>> ProjectableMarker<T> for PinMarker<P>
 where
     P::Output: Deref,
 {
@@ -124,6 +140,32 @@ where
     }
 }
 
+unsafe impl<P: DerefChain<M>, M> DerefChain<M> for Pin<P>
+where
+    P::Target: PinProjectable,
+{
+    type Target = P::Target;
+    type Marker = PinMarker<P::Marker>;
+
+    unsafe fn deref_chain(this: *mut Self, outer: M) -> (*mut Self::Target, Self::Marker) {
+        let (raw, marker) = P::deref_chain(this as *mut P, outer);
+        (raw, PinMarker(marker))
+    }
+}
+// pinning a pointer to an `Unpin` target doesn't restrict what can be done with it,
+// so the pointer inside is dereferenced as if there was no `Pin`
+unsafe impl<P: Deref + DerefProjectable> DerefProjectable for Pin<P>
+where
+    <P as Deref>::Target: Unpin,
+{
+    type Target = <P as DerefProjectable>::Target;
+    type Marker = P::Marker;
+
+    fn deref_raw(&self) -> (*mut Self::Target, Self::Marker) {
+        unsafe { &*(self as *const Self as *const P) }.deref_raw()
+    }
+}
+
 impl<'a, P> FinalizeProjection for Pin<P>
 where
     P: Deref,
@@ -177,6 +219,79 @@ impl<T> DerefMut for Unpinned<T> {
 }
 impl<T> DerefOwned for Unpinned<T> {}
 
+// pinned pointers are checked before `CustomWrapper`, so that a pointer to a type that doesn't support
+// pin projection becomes `NotPinProjectable` instead of failing later with a list of unsatisfied bounds
+unsafe impl<P: CustomWrapper<Output = Q>, Q: Deref<Target = T>, T: ?Sized + PinFieldsMap> Preprocess
+    for &&&&&ManuallyDrop<Pin<P>>
+{
+    type Output = Pin<Q>;
+
+    fn preprocess(&self) -> Self::Output {
+        unsafe { transmute_copy(*****self) }
+    }
+}
+unsafe impl<P: CustomWrapper<Output = Q>, Q: Deref<Target = T>, T: ?Sized + PinProjectable>
+    Preprocess for &&&&ManuallyDrop<Pin<P>>
+{
+    type Output = Pin<Q>;
+
+    fn preprocess(&self) -> Self::Output {
+        unsafe { transmute_copy(****self) }
+    }
+}
+unsafe impl<P: CustomWrapper<Output = Q>, Q: Deref<Target = T>, T: ?Sized> Preprocess
+    for &&&ManuallyDrop<Pin<P>>
+{
+    type Output = NotPinProjectable<T, Pin<Q>>;
+
+    fn preprocess(&self) -> Self::Output {
+        NotPinProjectable(PhantomData, unsafe { transmute_copy(***self) })
+    }
+}
+
+/// Type that shows up in compile errors when a pinned pointer is projected, but its target doesn't support
+/// pin projection, so the compile error says that `NotPinProjectable<T>` doesn't implement [`Projectable`].
+///
+/// Use [`pin_projectable!`], [`PinProjectable!`] or [`PinFields!`] on the type. They implement `Drop` themselves,
+/// so custom drop logic has to be moved into [`PinDrop`].
+/// ```rust,compile_fail
+/// use std::marker::PhantomPinned;
+/// use std::pin::Pin;
+/// use projecture::project;
+/// struct Foo(usize, PhantomPinned);
+/// impl Drop for Foo {
+///     fn drop(&mut self) {}
+/// }
+/// fn test(arg: Pin<&mut Foo>) {
+///     let _ = project!(arg -> 0);
+/// }
+/// ```
+/// ```rust
+/// use std::marker::PhantomPinned;
+/// use std::pin::Pin;
+/// use projecture::{pin_projectable, project, CallGuard, PinDrop};
+/// struct Foo(usize, PhantomPinned);
+/// pin_projectable! { Foo }
+/// impl PinDrop for Foo {
+///     fn drop(_self: CallGuard<Pin<&mut Self>>) {}
+/// }
+/// fn test(arg: Pin<&mut Foo>) {
+///     let _ = project!(arg -> 0);
+/// }
+/// ```
+pub struct NotPinProjectable<T: ?Sized, P = ()>(PhantomData<T>, ManuallyDrop<P>);
+
+// a pointer to an `Unpin` target is still dereferenced if there is another pointer inside,
+// like `Pin<RefMut<'_, Option<&mut T>>>`
+unsafe impl<T: ?Sized, P: DerefProjectable> DerefProjectable for NotPinProjectable<T, P> {
+    type Target = P::Target;
+    type Marker = P::Marker;
+
+    fn deref_raw(&self) -> (*mut Self::Target, Self::Marker) {
+        self.1.deref_raw()
+    }
+}
+
 // pub struct Pinned<T>(T);
 
 #[doc(hidden)]
@@ -186,6 +301,11 @@ pub struct PinnedMarker<'a>(&'a (), PhantomPinned);
 /// so we need some way to indicate that the type does not have incorrect `Unpin` implementation.
 /// Implemented by [`pin_projectable`] macro.
 pub unsafe trait PinProjectable {}
+// items of an array are always structurally pinned
+unsafe impl<T, const N: usize> PinProjectable for [T; N] {}
+unsafe impl<T> PinProjectable for [T] {}
+// to initialize pinned struct in place, fields of uninitialized struct are pinned the same way
+unsafe impl<T: PinProjectable> PinProjectable for MaybeUninit<T> {}
 
 /// For Pin projection to work soundly if struct wants to implement custom Drop it needs to
 /// always go through `Pin<&mut Self>`. So `Drop` implementation must directly delegate to `PinDrop`.
@@ -232,37 +352,31 @@ impl<T> CallGuard<T> {
 /// ```
 #[macro_export]
 macro_rules! pin_projectable {
-    ([ ! $($generics:tt)*] [$($type:tt)*] [] < $($tail:tt)* ) => { $crate::pin_projectable!{[ !! $($generics)* <] [ $($type)*] [] $($tail)*} };
-    ([ ! $($generics:tt)*] [$($type:tt)*] [] << $($tail:tt)* ) => { $crate::pin_projectable!{[ !! $($generics)* <] [ $($type)*] [] < $($tail)*} };
-    ([ !! $($generics:tt)*] [$($type:tt)*] [] > $($tail:tt)* ) => { $crate::pin_projectable!{[ ! $($generics)* >] [ $($type)*] [] $($tail)*} };
-    ([ !! $($generics:tt)*] [$($type:tt)*] [] >> $($tail:tt)* ) => { $crate::pin_projectable!{[ ! $($generics)* >] [ $($type)*] [] > $($tail)*} };
-    ([ !! $($generics:tt)*] [$($type:tt)*] [] $token:tt $($tail:tt)* ) => { $crate::pin_projectable!{[!! $($generics)* $token] [$($type)*] [] $($tail)*} };
-
-    ([ ! $($generics:tt)*] [$($type:tt)*] [] , $generic:tt $($tail:tt)* ) => { $crate::pin_projectable!{[ ! $($generics)* , $generic] [ $($type)* , $generic] [] $($tail)*} };
-    ([ ! $($generics:tt)*] [$($type:tt)*] [] > ) => { $crate::pin_projectable!{ [ $($generics)*] [ $($type)* > ] [] } };
-    ([ ! $($generics:tt)*] [$($type:tt)*] [] > where $($tail:tt)* ) => { $crate::pin_projectable!{[ $($generics)*] [ $($type)* >] [$($tail)*]  } };
-    ([ ! $($generics:tt)*] [$($type:tt)*] [] $token:tt $($tail:tt)* ) => { $crate::pin_projectable!{[! $($generics)* $token] [$($type)*] [] $($tail)*} };
-
-    ([$($generics:tt)*] [! $($type:tt)*] [] < $generic:tt $($tail:tt)* ) => { $crate::pin_projectable!{ [ ! $($generics)* $generic] [$($type)* < $generic] [] $($tail)*} };
-    ([$($generics:tt)*] [! $($type:tt)*] [] $name_part:tt $($tail:tt)* ) => { $crate::pin_projectable!{ [ $($generics)* ] [! $($type)* $name_part] [] $($tail)*} };
-    ([$($generics:tt)*] [! $($type:tt)*] [] ) => { $crate::pin_projectable!{ [ $($generics)* ] [ $($type)* ] [] } };
-
-    ([$($generics:tt)*] [$($type:tt)+] [$($where:tt)*] ) => {
-        impl<'__inner,$($generics)*> core::marker::Unpin for $($type)+ where $crate::pin::PinnedMarker<'__inner>:Unpin,$($where)*{}
+    ([] [$($generics:tt)*] [$($type:tt)+] [$($where:tt)*] ) => {
         unsafe impl<$($generics)*> $crate::pin::PinProjectable for $($type)+ where $($where)*{}
+        $crate::pin_projectable!{ [$crate::pin::PinnedMarker<'__inner>] [$($generics)*] [$($type)+] [$($where)*] }
+    };
+    // `$unpin` is a type that is `Unpin` whenever the struct can be `Unpin`
+    ([$($unpin:tt)+] [$($generics:tt)*] [$($type:tt)+] [$($where:tt)*] ) => {
+        impl<'__inner,$($generics)*> core::marker::Unpin for $($type)+ where $($unpin)+:Unpin,$($where)*{}
 
+        unsafe impl<$($generics)*> $crate::pin::DelegatePinDrop for $($type)+ where $($where)*{
+            unsafe fn delegate_pin_drop(this: *mut Self){
+                // `mut` is needed only for the fallback when there is no `PinDrop`
+                #[allow(unused_mut)]
+                let mut helper = &mut *(this as *mut $crate::Helper<$($type)+>);
+                use $crate::pin::PinDropDelegator;
+                helper.delegate()
+            }
+        }
         impl<$($generics)*> core::ops::Drop for $($type)+ where $($where)*{
             fn drop(&mut self){
-                unsafe {
-                    let mut helper = &mut *(self as *mut _ as *mut $crate::Helper<$($type)+>);
-                    use $crate::pin::PinDropDelegator;
-                    (&mut &mut helper).delegate()
-                }
+                unsafe { <Self as $crate::pin::DelegatePinDrop>::delegate_pin_drop(self) }
             }
         }
     };
 
-    ( $($tail:tt)* ) => { $crate::pin_projectable!{ [] [!] [] $($tail)* } };
+    ( $($tail:tt)* ) => { $crate::parse_generics!{ [pin_projectable] $($tail)* } };
 
 }
 
@@ -285,11 +399,604 @@ macro_rules! PinProjectable {
     ( [struct $($head:tt)*] ($($inner:tt)*) ; ) => {
         $crate::pin_projectable!{ $($head)* }
     };
-    ( [enum   $($head:tt)*] ($($inner:tt)*) ; ) => {
+    // where clause of a tuple struct
+    ( [struct $($head:tt)*] ($($inner:tt)*) where $($tail:tt)+ ) => {
+        $crate::PinProjectable!{ [struct $($head)* where] $($tail)+ }
+    };
+    ( [struct $($head:tt)*] ; ) => {
         $crate::pin_projectable!{ $($head)* }
     };
     ( [$($head:tt)*] $token:tt $($tail:tt)* ) => { $crate::PinProjectable!{ [$($head)* $token] $($tail)* } };
-    ( $($tail:tt)* ) => { $crate::PinProjectable!{ [] $($tail)* } };
+    ( $(#[$attr:meta])* $vis:vis struct $($tail:tt)* ) => { $crate::PinProjectable!{ [struct] $($tail)* } };
+    ( $(#[$attr:meta])* $vis:vis enum $($tail:tt)* ) => { $crate::PinProjectable!{ [enum] $($tail)* } };
+}
+
+/// Attribute version of [`PinProjectable!`] that chooses structurally pinned fields with field attributes,
+/// so struct doesn't need [`Unpinned`] wrappers in its field types.
+/// It removes these attributes from the struct, so it must be used with
+/// [`macro_rules_attribute::apply`](https://docs.rs/macro_rules_attribute/latest/macro_rules_attribute/attr.apply.html)
+/// instead of `derive`.
+///
+/// If any field is marked with `#[pin]`, only such fields are pinned and other fields are projected from
+/// `Pin<&mut Self>` as `&mut Field`. Otherwise fields marked with `#[unpin]` are projected as `&mut Field`
+/// and all other fields are pinned.
+/// Unlike with [`PinProjectable!`] struct is `Unpin` when all its pinned fields are `Unpin`.
+/// It also implements [`DropFields`], so the struct can be destructured after [`replace`].
+/// ```rust
+/// use projecture::{project, PinFields};
+/// use macro_rules_attribute::apply;
+/// use std::marker::PhantomPinned;
+/// use std::pin::Pin;
+///
+/// #[apply(PinFields!)]
+/// struct Foo<T> {
+///     #[pin]
+///     pinned: T,
+///     count: usize,
+/// }
+///
+/// fn bar(foo: Pin<&mut Foo<PhantomPinned>>) {
+///     project!(let Foo { pinned, count } = foo);
+///     let _: Pin<&mut PhantomPinned> = pinned;
+///     let _: &mut usize = count;
+/// }
+/// ```
+/// Such structs don't implement [`trait@PinProjectable`], so their pinned pointers can only be projected by [`project!`],
+/// and fields of a tuple struct can't be projected by position, e.g. after `..` in `Foo(.., last)` pattern.
+#[macro_export]
+#[allow(non_snake_case)]
+macro_rules! PinFields {
+    ( [$attrs:tt $item_vis:tt] [$($head:tt)*] { $($(#[$($attr:tt)*])* $vis:vis $field:ident : $type:ty),* $(,)? } ) => {
+        $crate::pin_fields_impl!{ sort [named [$attrs $item_vis] [$($head)*] []] [] [] [] $(([$([$($attr)*])*] [$vis] $field [$type]))* }
+    };
+    ( [$attrs:tt $item_vis:tt] [$($head:tt)*] ( $($(#[$($attr:tt)*])* $vis:vis $type:ty),* $(,)? ) [$($where:tt)*] ; ) => {
+        $crate::pin_fields_impl!{ sort [tuple [$attrs $item_vis] [$($head)*] [$($where)*]] [] [] [] $(([$([$($attr)*])*] [$vis] _ [$type]))* }
+    };
+    ( [$attrs:tt $item_vis:tt] [$($head:tt)*] ( $($fields:tt)* ) ; ) => {
+        $crate::PinFields!{ [$attrs $item_vis] [$($head)*] ( $($fields)* ) [] ; }
+    };
+    // where clause of a tuple struct
+    ( [$attrs:tt $item_vis:tt] [$($head:tt)*] ( $($fields:tt)* ) [$($where:tt)*] $token:tt $($tail:tt)+ ) => {
+        $crate::PinFields!{ [$attrs $item_vis] [$($head)*] ( $($fields)* ) [$($where)* $token] $($tail)+ }
+    };
+    ( [$attrs:tt $item_vis:tt] [$($head:tt)*] ( $($fields:tt)* ) where $($tail:tt)+ ) => {
+        $crate::PinFields!{ [$attrs $item_vis] [$($head)*] ( $($fields)* ) [where] $($tail)+ }
+    };
+    ( [$attrs:tt $item_vis:tt] [$($head:tt)*] $token:tt $($tail:tt)* ) => {
+        $crate::PinFields!{ [$attrs $item_vis] [$($head)* $token] $($tail)* }
+    };
+    ( $(#[$attr:meta])* $vis:vis struct $($tail:tt)* ) => { $crate::PinFields!{ [[$(#[$attr])*] [$vis]] [] $($tail)* } };
+    ( $(#[$attr:meta])* $vis:vis enum $($tail:tt)* ) => { compile_error!("`PinFields` supports only structs"); };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! pin_fields_impl {
+    // separates `#[pin]` and `#[unpin]` from other field attributes
+    (sort $args:tt $done:tt $pin:tt $unpin:tt ([$($attr:tt)*] $($field:tt)*) $($rest:tt)*) => {
+        $crate::pin_fields_impl!{ attrs [$args $done $pin $unpin [$($rest)*]] [] [] [$($attr)*] $($field)* }
+    };
+    (sort $args:tt [$($done:tt)*] $pin:tt $unpin:tt) => {
+        $crate::pin_fields_impl!{ mode $args $pin $unpin [] [] $($done)* }
+    };
+    (attrs $state:tt $kept:tt [$($tag:tt)*] [[pin] $($attr:tt)*] $($field:tt)*) => {
+        $crate::pin_fields_impl!{ attrs $state $kept [$($tag)* pin] [$($attr)*] $($field)* }
+    };
+    (attrs $state:tt $kept:tt [$($tag:tt)*] [[unpin] $($attr:tt)*] $($field:tt)*) => {
+        $crate::pin_fields_impl!{ attrs $state $kept [$($tag)* unpin] [$($attr)*] $($field)* }
+    };
+    (attrs $state:tt [$($kept:tt)*] $tag:tt [$next:tt $($attr:tt)*] $($field:tt)*) => {
+        $crate::pin_fields_impl!{ attrs $state [$($kept)* $next] $tag [$($attr)*] $($field)* }
+    };
+    (attrs [$args:tt [$($done:tt)*] $pin:tt $unpin:tt [$($rest:tt)*]] $kept:tt [] [] $($field:tt)*) => {
+        $crate::pin_fields_impl!{ sort $args [$($done)* (none $kept $($field)*)] $pin $unpin $($rest)* }
+    };
+    (attrs [$args:tt [$($done:tt)*] $pin:tt $unpin:tt [$($rest:tt)*]] $kept:tt [pin] [] $($field:tt)*) => {
+        $crate::pin_fields_impl!{ sort $args [$($done)* (pin $kept $($field)*)] [pin] $unpin $($rest)* }
+    };
+    (attrs [$args:tt [$($done:tt)*] $pin:tt $unpin:tt [$($rest:tt)*]] $kept:tt [unpin] [] $($field:tt)*) => {
+        $crate::pin_fields_impl!{ sort $args [$($done)* (unpin $kept $($field)*)] $pin [unpin] $($rest)* }
+    };
+    (attrs $state:tt $kept:tt [$($tag:tt)+] [] $($field:tt)*) => {
+        compile_error!("field can have only one `#[pin]` or `#[unpin]` attribute");
+    };
+
+    // chooses the kind of each field, pinned field types are collected to implement `Unpin`
+    (mode $args:tt [pin] [unpin] $($fields:tt)*) => {
+        compile_error!("`#[pin]` and `#[unpin]` attributes can't be used in the same struct");
+    };
+    (mode $args:tt [pin] [] [$($pinned:tt)*] [$($done:tt)*] (pin $($field:tt)*) $($rest:tt)*) => {
+        $crate::pin_fields_impl!{ pinned [mode $args [pin] []] [$($pinned)*] [$($done)*] $($field)* ; $($rest)* }
+    };
+    (mode $args:tt [pin] [] $pinned:tt [$($done:tt)*] (none $($field:tt)*) $($rest:tt)*) => {
+        $crate::pin_fields_impl!{ mode $args [pin] [] $pinned [$($done)* (UnpinnedField $($field)*)] $($rest)* }
+    };
+    (mode $args:tt [] $unpin:tt $pinned:tt [$($done:tt)*] (unpin $($field:tt)*) $($rest:tt)*) => {
+        $crate::pin_fields_impl!{ mode $args [] $unpin $pinned [$($done)* (UnpinnedField $($field)*)] $($rest)* }
+    };
+    (mode $args:tt [] $unpin:tt [$($pinned:tt)*] [$($done:tt)*] (none $($field:tt)*) $($rest:tt)*) => {
+        $crate::pin_fields_impl!{ pinned [mode $args [] $unpin] [$($pinned)*] [$($done)*] $($field)* ; $($rest)* }
+    };
+    (pinned [$($mode:tt)*] [$($pinned:tt)*] [$($done:tt)*] $attr:tt $vis:tt $field:tt [$type:ty] ; $($rest:tt)*) => {
+        $crate::pin_fields_impl!{ $($mode)* [$($pinned)* $type,] [$($done)* (PinnedField $attr $vis $field [$type])] $($rest)* }
+    };
+    (mode [$shape:ident $item:tt [$($head:tt)*] [$($where:tt)*]] $pin:tt $unpin:tt [$($pinned:tt)*] [$($fields:tt)*]) => {
+        $crate::pin_fields_impl!{ item $shape $item [$($head)*] [$($where)*] $($fields)* }
+        $crate::parse_generics!{ [pin_fields_impl impls $shape [$($pinned)*] [$($fields)*]] $($head)* $($where)* }
+    };
+
+    // emits the struct without `#[pin]` and `#[unpin]` attributes
+    (item named [[$($attr:tt)*] [$($vis:tt)*]] [$($head:tt)*] [] $(($kind:ident [$([$($field_attr:tt)*])*] [$($field_vis:tt)*] $field:ident [$type:ty]))*) => {
+        $($attr)* $($vis)* struct $($head)* { $( $(#[$($field_attr)*])* $($field_vis)* $field : $type, )* }
+    };
+    (item tuple [[$($attr:tt)*] [$($vis:tt)*]] [$($head:tt)*] [$($where:tt)*] $(($kind:ident [$([$($field_attr:tt)*])*] [$($field_vis:tt)*] _ [$type:ty]))*) => {
+        $($attr)* $($vis)* struct $($head)* ( $( $(#[$($field_attr)*])* $($field_vis)* $type, )* ) $($where)*;
+    };
+
+    ([impls $shape:ident [$($pinned:tt)*] [$($fields:tt)*]] $generics:tt $type:tt $where:tt) => {
+        $crate::pin_projectable!{ [$crate::pin::PinnedFields<'__inner, ($($pinned)*)>] $generics $type $where }
+        $crate::pin_fields_impl!{ map $shape $generics $type $where $($fields)* }
+        $crate::pin_fields_impl!{ drop_fields $shape [$generics $type $where] [] $($fields)* }
+    };
+    (drop_fields named $args:tt [$($done:tt)*] ($kind:ident $attr:tt $vis:tt $field:ident [$type:ty]) $($rest:tt)*) => {
+        $crate::pin_fields_impl!{ drop_fields named $args [$($done)* $field: $type,] $($rest)* }
+    };
+    (drop_fields tuple $args:tt [$($done:tt)*] ($kind:ident $attr:tt $vis:tt _ [$type:ty]) $($rest:tt)*) => {
+        $crate::pin_fields_impl!{ drop_fields tuple $args [$($done)* $type,] $($rest)* }
+    };
+    (drop_fields named [$($args:tt)*] [$($done:tt)*]) => {
+        $crate::drop_fields_impl!{ [named { $($done)* }] $($args)* }
+    };
+    (drop_fields tuple [$($args:tt)*] [$($done:tt)*]) => {
+        $crate::drop_fields_impl!{ [tuple ( $($done)* )] $($args)* }
+    };
+    // map has the same fields as the struct, but their types are field kinds
+    (map named [$($generics:tt)*] [$($type:tt)+] [$($where:tt)*] $(($kind:ident $attr:tt [$($field_vis:tt)*] $field:ident $field_type:tt))*) => {
+        const _: () = {
+            #[allow(dead_code)]
+            pub struct Map { $($($field_vis)* $field: $crate::pin::$kind,)* }
+            unsafe impl<$($generics)*> $crate::pin::PinFieldsMap for $($type)+ where $($where)* {
+                type Map = Map;
+            }
+        };
+    };
+    (map tuple [$($generics:tt)*] [$($type:tt)+] [$($where:tt)*] $(($kind:ident $attr:tt [$($field_vis:tt)*] _ $field_type:tt))*) => {
+        const _: () = {
+            #[allow(dead_code)]
+            pub struct Map($($($field_vis)* $crate::pin::$kind,)*);
+            unsafe impl<$($generics)*> $crate::pin::PinFieldsMap for $($type)+ where $($where)* {
+                type Map = Map;
+            }
+        };
+    };
+}
+
+/// Implemented by [`PinFields!`], `Map` is a struct with the same fields, which types are
+/// [`PinnedField`] or [`UnpinnedField`].
+///
+/// # Safety
+/// Struct must not implement [`trait@PinProjectable`], must be `Unpin` only if its pinned fields are,
+/// and its `Drop` implementation must delegate to [`PinDrop`].
+#[doc(hidden)]
+pub unsafe trait PinFieldsMap {
+    type Map;
+}
+
+unsafe impl<T: PinFieldsMap> PinFieldsMap for MaybeUninit<T> {
+    type Map = T::Map;
+}
+
+#[doc(hidden)]
+pub struct PinnedField;
+#[doc(hidden)]
+pub struct UnpinnedField;
+// field of a tuple struct that was found by its position
+#[doc(hidden)]
+pub struct UnknownPinField;
+
+#[doc(hidden)]
+#[allow(dead_code)]
+pub struct PinnedFields<'a, T: ?Sized>(&'a (), T);
+
+// `Pin` of a `PinFieldsMap` struct, it can't be used to project fields until the field kind is known
+#[doc(hidden)]
+#[repr(transparent)]
+pub struct PinFieldsMarker<T>(T);
+unsafe impl<P: Deref<Target = T> + Projectable<Target = T>, T: ?Sized + PinFieldsMap> Projectable
+    for &&Pin<P>
+{
+    type Target = T;
+    type Marker = PinFieldsMarker<P::Marker>;
+
+    fn get_raw(&self) -> (*mut Self::Target, Self::Marker) {
+        let res = unsafe { transmute_copy::<_, P>(**self) }.get_raw();
+        (res.0, PinFieldsMarker(res.1))
+    }
+}
+unsafe impl<'a, P: Deref<Target = T>, T: ?Sized + PinFieldsMap> Projectable
+    for &&Helper<&'a Pin<P>>
+{
+    type Target = T;
+    type Marker = PinFieldsMarker<Marker<&'a ()>>;
+
+    fn get_raw(&self) -> (*mut Self::Target, Self::Marker) {
+        (
+            self.0.as_ref().get_ref() as *const _ as _,
+            PinFieldsMarker(Marker::new()),
+        )
+    }
+}
+
+unsafe impl<M: WrapMarker<MaybeUninit<()>>> WrapMarker<MaybeUninit<()>> for PinFieldsMarker<M> {
+    type Output = PinFieldsMarker<M::Output>;
+
+    fn wrap_marker(self) -> Self::Output {
+        PinFieldsMarker(self.0.wrap_marker())
+    }
+}
+
+#[doc(hidden)]
+pub trait LookupPinFields {
+    type Map;
+    type Positional;
+    fn pin_fields_map(&self) -> *mut Self::Map {
+        NonNull::dangling().as_ptr()
+    }
+    fn positional_pin_field(&self) -> PhantomData<Self::Positional> {
+        PhantomData
+    }
+}
+impl<T: PinFieldsMap> LookupPinFields for &*mut T {
+    type Map = T::Map;
+    type Positional = UnknownPinField;
+}
+// for other structs field kind is just a type of the field
+impl<T> LookupPinFields for *mut T {
+    type Map = T;
+    type Positional = PinnedField;
+}
+
+#[doc(hidden)]
+pub fn pin_field_kind<K: ?Sized>(_field: *mut K) -> PhantomData<K> {
+    PhantomData
+}
+
+/// Selects marker to project a field of the given kind.
+#[doc(hidden)]
+pub trait SelectPinField<'a> {
+    type Marker;
+    fn select_marker(&self) -> &'a Self::Marker;
+}
+impl<'a, M> SelectPinField<'a> for &(PhantomData<PinnedField>, &'a PinFieldsMarker<M>) {
+    type Marker = PinMarker<M>;
+
+    fn select_marker(&self) -> &'a Self::Marker {
+        unsafe { &*(self.1 as *const PinFieldsMarker<M> as *const PinMarker<M>) }
+    }
+}
+impl<'a, M> SelectPinField<'a> for &(PhantomData<UnpinnedField>, &'a PinFieldsMarker<M>) {
+    type Marker = M;
+
+    fn select_marker(&self) -> &'a Self::Marker {
+        &self.1 .0
+    }
+}
+// `Pin` can also come from a dereference of `&mut Pin<P>`
+impl<'a, M> SelectPinField<'a> for &(PhantomData<UnpinnedField>, &'a PinMarker<M>) {
+    type Marker = M;
+
+    fn select_marker(&self) -> &'a Self::Marker {
+        &self.1 .0
+    }
+}
+impl<'a, M> SelectPinField<'a> for &(PhantomData<UnknownPinField>, &'a PinMarker<M>) {
+    type Marker = PinFieldsMarker<M>;
+
+    fn select_marker(&self) -> &'a Self::Marker {
+        unsafe { &*(self.1 as *const PinMarker<M> as *const PinFieldsMarker<M>) }
+    }
+}
+impl<'a, K: ?Sized, M> SelectPinField<'a> for (PhantomData<K>, &'a M) {
+    type Marker = M;
+
+    fn select_marker(&self) -> &'a Self::Marker {
+        self.1
+    }
+}
+
+/// Replaces the pinned value with `new`, and returns the old one to be destructured with [`project!`].
+///
+/// Old value is destroyed like it would have been by `drop`, except that its unpinned fields are moved out
+/// instead of being dropped. [`PinDrop`] implementation runs first, then pinned fields are projected as `PhantomData`.
+/// Unpinned fields are ones marked by [`PinFields!`] attributes or wrapped in [`Unpinned`].
+/// Fields that were not mentioned in the pattern are dropped in place, so the struct must implement [`DropFields`]
+/// ([`PinFields!`] implements it, otherwise it can be derived with [`Fields!`](crate::Fields)).
+/// Pinned fields from the pattern are dropped in place after them, when the projection is finished,
+/// and only then `new` is written. If one of the drops panics, the other fields are still dropped and `new` is still written.
+/// Fields of a tuple struct that are found by position, e.g. after `..` in `Foo(.., last)`, are always treated as pinned.
+/// Enums can be matched with `project!(match ..)`, variants that are not destructured are dropped in place.
+/// ```rust
+/// use projecture::{project, PinFields};
+/// use projecture::pin::replace;
+/// use macro_rules_attribute::apply;
+/// use std::future::{pending, Pending};
+/// use std::marker::PhantomData;
+/// use std::pin::Pin;
+///
+/// #[apply(PinFields!)]
+/// struct Running {
+///     #[pin]
+///     task: Pending<()>,
+///     name: String,
+/// }
+///
+/// fn finish(running: Pin<&mut Running>) -> String {
+///     let done = Running { task: pending(), name: String::new() };
+///     project!(let Running { task, name } = replace(running, done));
+///     let _: PhantomData<Pending<()>> = task;
+///     name
+/// }
+/// let mut running = Box::pin(Running { task: pending(), name: "first".to_owned() });
+/// assert_eq!(finish(running.as_mut()), "first");
+/// assert_eq!(running.name, "");
+/// ```
+/// State machine that moves the output out of its previous state:
+/// ```rust
+/// use projecture::{project, Fields, PinProjectable, Unpinned};
+/// use projecture::pin::replace;
+/// use macro_rules_attribute::derive;
+/// use std::pin::Pin;
+///
+/// #[derive(Fields!, PinProjectable!)]
+/// enum State<F> {
+///     Running(F, Unpinned<String>),
+///     Done,
+/// }
+///
+/// fn finish<F>(state: Pin<&mut State<F>>) -> Option<String> {
+///     project!(match replace(state, State::Done) {
+///         State::Running(_, output) => Some(output.0),
+///         State::Done => None,
+///     })
+/// }
+/// let mut state = Box::pin(State::Running((), Unpinned("output".to_owned())));
+/// assert_eq!(finish(state.as_mut()).as_deref(), Some("output"));
+/// assert_eq!(finish(state.as_mut()), None);
+/// ```
+pub fn replace<'a, T>(this: Pin<&'a mut T>, new: T) -> Replace<'a, T>
+where
+    T: DelegatePinDrop + DropFields,
+{
+    Replace {
+        ptr: unsafe { Pin::get_unchecked_mut(this) },
+        new: ManuallyDrop::new(new),
+        lifetime: PhantomData,
+    }
+}
+
+/// Result of [`replace`], if it is dropped without being projected, old value is dropped as a whole.
+pub struct Replace<'a, T: DelegatePinDrop + DropFields> {
+    ptr: *mut T,
+    new: ManuallyDrop<T>,
+    lifetime: PhantomData<&'a mut T>,
+}
+impl<'a, T: DelegatePinDrop + DropFields> Drop for Replace<'a, T> {
+    fn drop(&mut self) {
+        let write = WriteNew(self.ptr, &mut self.new);
+        unsafe { drop_in_place(write.0) }
+    }
+}
+
+// writes the new value even if dropping the old one panics, so the pinned place always holds a valid value
+struct WriteNew<T>(*mut T, *mut ManuallyDrop<T>);
+impl<T> Drop for WriteNew<T> {
+    fn drop(&mut self) {
+        unsafe { ptr::write(self.0, ManuallyDrop::take(&mut *self.1)) }
+    }
+}
+unsafe impl<'a, T: DelegatePinDrop + DropFields> CustomWrapper for Replace<'a, T> {
+    type Output = Helper<Replace<'a, T>>;
+}
+unsafe impl<'a, T: DelegatePinDrop + DropFields> Projectable for Helper<Replace<'a, T>> {
+    type Target = T;
+    type Marker = ReplaceToken<'a, T>;
+
+    fn get_raw(&self) -> (*mut Self::Target, Self::Marker) {
+        (self.0.ptr, ReplaceToken(&self.0.new, PhantomData))
+    }
+}
+
+// `get_raw` can be called any number of times, so it only points to `new`,
+// `PinDrop` runs and `new` is moved out when `project!` unwraps the target and then forgets the `Replace`
+#[doc(hidden)]
+pub struct ReplaceToken<'a, T>(*const ManuallyDrop<T>, PhantomData<&'a mut T>);
+impl<'a, T: DelegatePinDrop + DropFields> UnwrapTarget
+    for &ManuallyDrop<(*mut T, ReplaceToken<'a, T>)>
+{
+    type Target = T;
+    type Marker = ReplaceMarker<'a, T>;
+
+    unsafe fn unwrap_target(&self) -> (*mut Self::Target, Self::Marker) {
+        let (ptr, ref token) = ***self;
+        let marker = ReplaceMarker {
+            ptr,
+            new: ptr::read(token.0),
+            pinned: DropPinnedMarker(Cell::new(ptr::null())),
+            lifetime: PhantomData,
+        };
+        // if `PinDrop` panics, fields are dropped and then `marker` writes the new value
+        struct DropFieldsOnUnwind<T: DropFields>(*mut T);
+        impl<T: DropFields> Drop for DropFieldsOnUnwind<T> {
+            fn drop(&mut self) {
+                unsafe { T::drop_fields_except::<()>(self.0) }
+            }
+        }
+        let guard = DropFieldsOnUnwind(ptr);
+        T::delegate_pin_drop(ptr);
+        core::mem::forget(guard);
+        (ptr, marker)
+    }
+}
+
+// writes the new value when the projection is finished, after the pinned fields are dropped
+#[doc(hidden)]
+pub struct ReplaceMarker<'a, T> {
+    ptr: *mut T,
+    new: ManuallyDrop<T>,
+    pinned: DropPinnedMarker,
+    lifetime: PhantomData<&'a mut T>,
+}
+impl<'a, T> Drop for ReplaceMarker<'a, T> {
+    fn drop(&mut self) {
+        let _write = WriteNew(self.ptr, &mut self.new);
+        unsafe { self.pinned.drop_fields() }
+    }
+}
+impl<T: DropFields> DropRemainingFields for &(*mut T, &ReplaceMarker<'_, T>) {
+    unsafe fn drop_remaining<M: MovedFields>(&self) {
+        T::drop_fields_except::<M>(self.0)
+    }
+}
+// `PinDrop` has already run, so variants that are not destructured are dropped field by field
+unsafe impl<'a, T: DropFields> SupportsEnums for ReplaceMarker<'a, T> {}
+impl<'a, T: DropFields> ProjectableMarker<T> for ReplaceMarker<'a, T> {
+    type Output = PhantomData<T>;
+
+    unsafe fn from_raw(&self, raw: *mut T) -> Self::Output {
+        T::drop_fields_except::<()>(raw);
+        PhantomData
+    }
+}
+
+// Pinned fields are dropped only when the projection is finished, in case the pattern panics
+// (e.g. a field with a `Drop` that panics) while other fields are not yet projected.
+// Each field gets a `PinnedFieldGuard` local in the scope of `project!`, linked into this list,
+// which drops the field on unwind if the marker has not done it yet.
+#[doc(hidden)]
+pub struct DropPinnedMarker(Cell<*const PinnedFieldGuard>);
+impl DropPinnedMarker {
+    // drops fields in reverse order of projection, if one of them panics the rest are still dropped
+    unsafe fn drop_fields(&self) {
+        struct Rest<'a>(&'a DropPinnedMarker);
+        impl Drop for Rest<'_> {
+            fn drop(&mut self) {
+                unsafe { self.0.drop_fields() }
+            }
+        }
+        while let Some(guard) = self.0.get().as_ref() {
+            self.0.set(guard.next.get());
+            guard.dropped.set(true);
+            let rest = Rest(self);
+            (guard.drop)(guard.field);
+            core::mem::forget(rest);
+        }
+    }
+}
+impl<T> ProjectableMarker<T> for DropPinnedMarker {
+    type Output = PhantomData<T>;
+
+    unsafe fn from_raw(&self, _raw: *mut T) -> Self::Output {
+        PhantomData
+    }
+}
+
+#[doc(hidden)]
+pub struct PinnedFieldGuard {
+    list: *const DropPinnedMarker,
+    field: *mut (),
+    drop: unsafe fn(*mut ()),
+    next: Cell<*const PinnedFieldGuard>,
+    dropped: Cell<bool>,
+}
+impl PinnedFieldGuard {
+    /// # Safety
+    /// Guard must not be moved after it is linked, and the marker must not be moved until then.
+    pub unsafe fn link(&self) {
+        self.next.set((*self.list).0.get());
+        (*self.list).0.set(self);
+    }
+}
+impl Drop for PinnedFieldGuard {
+    // only reached without `dropped` on unwind, guards are dropped in reverse order so this one is the head of the list
+    fn drop(&mut self) {
+        if !self.dropped.get() {
+            self.dropped.set(true);
+            unsafe {
+                debug_assert!(ptr::eq((*self.list).0.get(), self));
+                (*self.list).0.set(self.next.get());
+                (self.drop)(self.field)
+            }
+        }
+    }
+}
+
+#[doc(hidden)]
+pub struct NoFieldGuard;
+impl NoFieldGuard {
+    /// # Safety
+    /// Does nothing, it is `unsafe` only to be called like [`PinnedFieldGuard::link`].
+    pub unsafe fn link(&self) {}
+}
+
+// `project!` creates a guard for each field it projects, which is a no-op except for pinned fields of `replace`
+#[doc(hidden)]
+pub trait SelectFieldGuard {
+    type Guard;
+    /// # Safety
+    /// Pointer must be the one that was passed to `from_raw` of the marker.
+    unsafe fn field_guard(&self) -> Self::Guard;
+}
+impl<U: ?Sized, M: ?Sized> SelectFieldGuard for (*mut U, &M) {
+    type Guard = NoFieldGuard;
+
+    unsafe fn field_guard(&self) -> Self::Guard {
+        NoFieldGuard
+    }
+}
+impl<U> SelectFieldGuard for &(*mut U, &DropPinnedMarker) {
+    type Guard = PinnedFieldGuard;
+
+    unsafe fn field_guard(&self) -> Self::Guard {
+        unsafe fn drop_erased<U>(ptr: *mut ()) {
+            drop_field(ptr as *mut U)
+        }
+        PinnedFieldGuard {
+            list: self.1,
+            field: self.0 as *mut (),
+            drop: drop_erased::<U>,
+            next: Cell::new(ptr::null()),
+            dropped: Cell::new(false),
+        }
+    }
+}
+
+impl<'a, T> SelectPinField<'a> for &(PhantomData<UnpinnedField>, &'a ReplaceMarker<'_, T>) {
+    type Marker = Marker<()>;
+
+    fn select_marker(&self) -> &'a Self::Marker {
+        &Marker(PhantomData)
+    }
+}
+impl<'a, T, U> SelectPinField<'a> for &(PhantomData<Unpinned<U>>, &'a ReplaceMarker<'_, T>) {
+    type Marker = Marker<()>;
+
+    fn select_marker(&self) -> &'a Self::Marker {
+        &Marker(PhantomData)
+    }
+}
+// everything else is pinned
+impl<'a, K: ?Sized, T> SelectPinField<'a> for &&(PhantomData<K>, &'a ReplaceMarker<'_, T>) {
+    type Marker = DropPinnedMarker;
+
+    fn select_marker(&self) -> &'a Self::Marker {
+        &self.1.pinned
+    }
+}
+
+// lets generic code run `PinDrop`, which is otherwise found only for concrete types
+#[doc(hidden)]
+pub unsafe trait DelegatePinDrop {
+    unsafe fn delegate_pin_drop(this: *mut Self);
 }
 
 #[doc(hidden)]
@@ -307,6 +1014,383 @@ unsafe impl<T: PinDrop> PinDropDelegator for Helper<T> {
     }
 }
 
+//---------------------
+/// Uninitialized place that can be initialized with [`write`](fn@write).
+///
+/// # Safety
+/// `into_raw` must return a pointer that is valid for writes for `'a`.
+pub unsafe trait UninitSlot<'a> {
+    type Target;
+    /// [`PinnedSlot`] if the slot is pinned, [`UnpinnedSlot`] otherwise
+    type Kind;
+    fn into_raw(self) -> *mut Self::Target;
+}
+unsafe impl<'a, T> UninitSlot<'a> for &'a mut MaybeUninit<T> {
+    type Target = T;
+    type Kind = UnpinnedSlot;
+
+    fn into_raw(self) -> *mut Self::Target {
+        self.as_mut_ptr()
+    }
+}
+// value is moved into the slot before anything could have pinned it, just like with `Pin::set`
+unsafe impl<'a, T> UninitSlot<'a> for Pin<&'a mut MaybeUninit<T>> {
+    type Target = T;
+    type Kind = PinnedSlot;
+
+    fn into_raw(self) -> *mut Self::Target {
+        unsafe { self.get_unchecked_mut() }.as_mut_ptr()
+    }
+}
+
+/// Kind of [`Written`] proof for a slot that was pinned.
+pub struct PinnedSlot;
+/// Kind of [`Written`] proof for a slot that was not pinned, value can't be pinned in it.
+pub struct UnpinnedSlot;
+
+/// Proof that the slot, which is borrowed for `'a`, is initialized.
+/// Struct can be put together from such proofs for all of its fields with [`pin_init!`].
+/// If it is dropped, the value is leaked.
+///
+/// `K` is [`PinnedSlot`] only if the slot was pinned, struct proof is pinned only if all its field proofs are,
+/// except that fields of `Unpin` types are always considered pinned.
+pub struct Written<'a, T, K = PinnedSlot>(*mut T, PhantomData<(&'a mut T, K)>);
+impl<'a, T, K> Written<'a, T, K> {
+    // used only to type check struct literal in `pin_init!`
+    #[doc(hidden)]
+    pub fn never(&self) -> T {
+        unreachable!()
+    }
+
+    // ends the borrow of the slot, so `pin_init!` can borrow the whole struct again
+    #[doc(hidden)]
+    pub fn into_raw(self) -> (*mut T, PhantomData<K>) {
+        (self.0, PhantomData)
+    }
+}
+
+/// Initializes an uninitialized slot which is usually a field projected from `Pin<&mut MaybeUninit<Struct>>`.
+///
+/// The slot can already contain a value, which will be leaked then. See [`pin_init!`] for an example.
+pub fn write<'a, S: UninitSlot<'a>>(slot: S, value: S::Target) -> Written<'a, S::Target, S::Kind> {
+    let ptr = slot.into_raw();
+    unsafe { ptr::write(ptr, value) };
+    Written(ptr, PhantomData)
+}
+
+/// Initializes struct in place from the [`Written`] proofs of all its fields.
+///
+/// `pin_init!(Struct { field: proof, .. })` gives a proof for the whole struct, so it can be used
+/// for the nested struct fields. Struct literal syntax is used even for tuple structs (`Struct { 0: proof }`)
+/// and it panics if the proofs are not for fields of the same value.
+///
+/// `pin_init!(let name = slot => Struct { field: proof, .. })` or `pin_init!(let name = slot => proof)`
+/// gives `name: Pin<&mut Struct>` and value is dropped at the end of the current scope, while the slot stays borrowed.
+/// `slot` is the `Pin<&mut MaybeUninit<Struct>>` the fields were projected from with `slot.as_mut()`,
+/// `name` borrows the struct from it again, so the proofs only need to point to the fields.
+/// If `slot` or the proof is not pinned (see [`Written`]), `name` is just `&mut Struct`,
+/// and `slot` can then also be a `MaybeUninit<Struct>` or `&mut MaybeUninit<Struct>`.
+/// Slot can also be a `Pin<Box<MaybeUninit<Struct>>>`, see [`init_box`].
+///
+/// Fields projected from `Pin<&mut MaybeUninit<Struct>>` are `Pin<&mut MaybeUninit<Field>>` if they are structurally pinned,
+/// so self-referential structs can be initialized right at their final location.
+/// ```rust
+/// use projecture::{pin_init, project, PinProjectable, Unpinned};
+/// use projecture::pin::write;
+/// use macro_rules_attribute::derive;
+/// use std::marker::PhantomPinned;
+/// use std::mem::MaybeUninit;
+/// use std::pin::Pin;
+/// use std::ptr;
+///
+/// #[derive(PinProjectable!)]
+/// struct Node {
+///     next: *const Node,
+///     _pin: PhantomPinned,
+/// }
+/// #[derive(PinProjectable!)]
+/// struct List {
+///     head: Node,
+///     len: Unpinned<usize>,
+/// }
+///
+/// let mut slot = MaybeUninit::<List>::uninit();
+/// // `slot` is not moved until the value is dropped because it stays borrowed
+/// let mut slot = unsafe { Pin::new_unchecked(&mut slot) };
+/// project!(let List { head, len } = slot.as_mut());
+/// let head_ptr = head.as_ptr();
+/// project!(let Node { next, _pin } = head);
+/// let head = pin_init!(Node {
+///     next: write(next, head_ptr),
+///     _pin: write(_pin, PhantomPinned),
+/// });
+/// pin_init!(let list = slot => List { head, len: write(len, Unpinned(1)) });
+/// let list: Pin<&mut List> = list;
+/// assert!(ptr::eq(list.head.next, &list.head));
+/// ```
+/// Value initialized in a slot that is not pinned is not pinned either:
+/// ```rust
+/// # use projecture::{pin_init, project, PinProjectable};
+/// # use projecture::pin::write;
+/// # use macro_rules_attribute::derive;
+/// # use std::marker::PhantomPinned;
+/// # use std::mem::MaybeUninit;
+/// #[derive(PinProjectable!)]
+/// struct Waiter(usize, PhantomPinned);
+///
+/// let mut slot = MaybeUninit::<Waiter>::uninit();
+/// project!(let Waiter(count, pinned) = &mut slot);
+/// pin_init!(let waiter = slot => Waiter { 0: write(count, 1), 1: write(pinned, PhantomPinned) });
+/// let waiter: &mut Waiter = waiter;
+/// # let _ = waiter;
+/// ```
+/// Missing fields don't compile:
+/// ```rust,compile_fail
+/// # use projecture::{pin_init, pin_projectable, project};
+/// # use projecture::pin::write;
+/// # use std::mem::MaybeUninit;
+/// struct Foo(usize, usize);
+/// pin_projectable!(Foo);
+/// let mut slot = MaybeUninit::<Foo>::uninit();
+/// project!(let Foo(first, _) = &mut slot);
+/// pin_init!(let foo = slot => Foo { 0: write(first, 1) });
+/// ```
+#[macro_export]
+macro_rules! pin_init {
+    (let $name:ident = $slot:expr => $($tail:tt)+) => {
+        // proof is consumed first, so the borrow of the fields ends before `$slot` is borrowed again
+        let written = $crate::pin_init!($($tail)+).into_raw();
+        let slot = $crate::pin::ReborrowSlot::reborrow(&mut $slot);
+        // guard can't be named outside of the macro, so it can't be leaked
+        let mut guard = unsafe { $crate::pin::InitGuard::new(slot, written) };
+        let $name = guard.get();
+    };
+    (let mut $name:ident = $slot:expr => $($tail:tt)+) => {
+        let written = $crate::pin_init!($($tail)+).into_raw();
+        let slot = $crate::pin::ReborrowSlot::reborrow(&mut $slot);
+        // guard can't be named outside of the macro, so it can't be leaked
+        let mut guard = unsafe { $crate::pin::InitGuard::new(slot, written) };
+        let mut $name = guard.get();
+    };
+    ($($tail:tt)+) => {
+        $crate::pin_init_impl! { path [] $($tail)+ }
+    };
+}
+
+// each proof is put into its own `token` variable, which are distinct because of hygiene
+#[doc(hidden)]
+#[macro_export]
+macro_rules! pin_init_impl {
+    (path [$($path:tt)+] { $($fields:tt)+ }) => {{
+        $crate::pin_init_impl! { [$($path)+] [] $($fields)+ }
+    }};
+    (path [$($path:tt)*] $next:tt $($tail:tt)*) => {
+        $crate::pin_init_impl! { path [$($path)* $next] $($tail)* }
+    };
+    // not a struct literal, so it is already a proof
+    (path [$($written:tt)+]) => {
+        $($written)+
+    };
+    ([$($path:tt)+] [$(($field:tt $token:ident))*] $next:tt : $value:expr $(, $($tail:tt)*)?) => {
+        let token = $value;
+        $crate::pin_init_impl! { [$($path)+] [$(($field $token))* ($next token)] $($($tail)*)? }
+    };
+    ([$($path:tt)+] [$(($field:tt $token:ident))*] $next:ident $(, $($tail:tt)*)?) => {
+        let token = $next;
+        $crate::pin_init_impl! { [$($path)+] [$(($field $token))* ($next token)] $($($tail)*)? }
+    };
+    ([$($path:tt)+] [$(($field:tt $token:ident))+]) => {
+        // struct literal fails to compile if some field is missing
+        let mut base = $crate::pin::init_base(|| $($path)+ { $($field: $token.never()),+ });
+        let base = base.as_mut_ptr();
+        let root = $crate::pin::no_root(base);
+        $(
+            let kind = {
+                use $crate::pin::WrittenKind;
+                (&&$crate::pin::written_kind(&$token)).kind()
+            };
+            let root = $crate::pin::written_root($token, kind, base, unsafe { core::ptr::addr_of_mut!((*base).$field) }, root);
+        )+
+        root.unwrap()
+    };
+}
+
+#[doc(hidden)]
+pub fn init_base<T>(_literal: impl FnOnce() -> T) -> MaybeUninit<T> {
+    MaybeUninit::uninit()
+}
+
+#[doc(hidden)]
+pub fn no_root<'a, T>(_base: *mut T) -> Option<Written<'a, T, PinnedSlot>> {
+    None
+}
+
+#[doc(hidden)]
+pub fn written_kind<T, K>(_written: &Written<'_, T, K>) -> PhantomData<(T, K)> {
+    PhantomData
+}
+
+#[doc(hidden)]
+pub trait WrittenKind {
+    type Kind;
+    fn kind(&self) -> PhantomData<Self::Kind> {
+        PhantomData
+    }
+}
+impl<T, K> WrittenKind for PhantomData<(T, K)> {
+    type Kind = K;
+}
+// slot of `Unpin` field could have been pinned with `Pin::new` anyway
+impl<T: Unpin, K> WrittenKind for &PhantomData<(T, K)> {
+    type Kind = PinnedSlot;
+}
+
+// struct proof is pinned only if all field proofs are
+#[doc(hidden)]
+pub trait CombineSlots<K> {
+    type Output;
+}
+impl CombineSlots<PinnedSlot> for PinnedSlot {
+    type Output = PinnedSlot;
+}
+impl CombineSlots<UnpinnedSlot> for PinnedSlot {
+    type Output = UnpinnedSlot;
+}
+impl<K> CombineSlots<K> for UnpinnedSlot {
+    type Output = UnpinnedSlot;
+}
+
+// finds the start of the struct from the field proof and checks that it is the same for all fields
+#[doc(hidden)]
+pub fn written_root<'a, T, F, FW, FK, K: CombineSlots<FK>>(
+    field: Written<'a, F, FW>,
+    _kind: PhantomData<FK>,
+    base: *mut T,
+    field_base: *mut F,
+    root: Option<Written<'a, T, K>>,
+) -> Option<Written<'a, T, K::Output>> {
+    let offset = field_base as usize - base as usize;
+    let ptr = (field.0 as *mut u8).wrapping_sub(offset) as *mut T;
+    if let Some(root) = root {
+        assert!(
+            ptr::eq(root.0, ptr),
+            "`pin_init!` got fields that were written to different places"
+        );
+    }
+    Some(Written(ptr, PhantomData))
+}
+
+// `pin_init!(let name = slot => ..)` borrows `slot` itself, so it can use it as a whole.
+// Pointer in a proof assembled from fields is only used to check the address,
+// because it is derived from a borrow of a single field.
+#[doc(hidden)]
+pub trait ReborrowSlot<'a> {
+    type Slot: UninitSlot<'a>;
+    fn reborrow(self) -> Self::Slot;
+}
+impl<'a, T> ReborrowSlot<'a> for &'a mut MaybeUninit<T> {
+    type Slot = Self;
+
+    fn reborrow(self) -> Self::Slot {
+        self
+    }
+}
+impl<'a, T> ReborrowSlot<'a> for &'a mut &mut MaybeUninit<T> {
+    type Slot = &'a mut MaybeUninit<T>;
+
+    fn reborrow(self) -> Self::Slot {
+        &mut **self
+    }
+}
+impl<'a, T> ReborrowSlot<'a> for &'a mut Pin<&mut MaybeUninit<T>> {
+    type Slot = Pin<&'a mut MaybeUninit<T>>;
+
+    fn reborrow(self) -> Self::Slot {
+        self.as_mut()
+    }
+}
+
+// drops initialized value at the end of the scope
+#[doc(hidden)]
+pub struct InitGuard<'a, T, K>(*mut T, PhantomData<(&'a mut T, K)>);
+impl<'a, T> InitGuard<'a, T, ()> {
+    /// # Safety
+    /// Guard must not be leaked, `written` must be a proof that was just consumed.
+    pub unsafe fn new<S, K>(
+        slot: S,
+        written: (*mut T, PhantomData<K>),
+    ) -> InitGuard<'a, T, <S::Kind as CombineSlots<K>>::Output>
+    where
+        S: UninitSlot<'a, Target = T>,
+        S::Kind: CombineSlots<K>,
+    {
+        let ptr = slot.into_raw();
+        assert!(
+            ptr::eq(ptr, written.0),
+            "`pin_init!` got proof for some other value"
+        );
+        InitGuard(ptr, PhantomData)
+    }
+}
+impl<'a, T> InitGuard<'a, T, PinnedSlot> {
+    pub fn get(&mut self) -> Pin<&mut T> {
+        unsafe { Pin::new_unchecked(&mut *self.0) }
+    }
+}
+impl<'a, T> InitGuard<'a, T, UnpinnedSlot> {
+    pub fn get(&mut self) -> &mut T {
+        unsafe { &mut *self.0 }
+    }
+}
+impl<'a, T, K> Drop for InitGuard<'a, T, K> {
+    fn drop(&mut self) {
+        unsafe { drop_in_place(self.0) }
+    }
+}
+
+/// Initializes boxed value in place with `init` and gives it back as `Pin<Box<T>>`.
+///
+/// Panics if `init` returned proof for some other value.
+/// ```rust
+/// use projecture::{pin_init, project, PinFields};
+/// use projecture::pin::{init_box, write};
+/// use macro_rules_attribute::apply;
+/// use std::marker::PhantomPinned;
+/// use std::mem::MaybeUninit;
+/// use std::pin::Pin;
+///
+/// #[apply(PinFields!)]
+/// struct Waiter {
+///     #[pin]
+///     pinned: PhantomPinned,
+///     this: *const Waiter,
+/// }
+///
+/// let waiter = init_box(Box::pin(MaybeUninit::uninit()), |slot| {
+///     let this = slot.as_ptr();
+///     project!(let Waiter { pinned, this: this_slot } = slot);
+///     let _: Pin<&mut MaybeUninit<PhantomPinned>> = pinned;
+///     let _: &mut MaybeUninit<*const Waiter> = this_slot;
+///     pin_init!(Waiter { pinned: write(pinned, PhantomPinned), this: write(this_slot, this) })
+/// });
+/// assert_eq!(waiter.this, &*waiter as *const Waiter);
+/// ```
+#[cfg(feature = "std")]
+pub fn init_box<T, K>(
+    mut slot: Pin<Box<MaybeUninit<T>>>,
+    init: impl for<'b> FnOnce(Pin<&'b mut MaybeUninit<T>>) -> Written<'b, T, K>,
+) -> Pin<Box<T>> {
+    let ptr = init(slot.as_mut()).0;
+    assert!(
+        ptr::eq(ptr, slot.as_ptr()),
+        "`init_box` got proof for some other value"
+    );
+    unsafe {
+        let raw = Box::into_raw(Pin::into_inner_unchecked(slot));
+        Pin::new_unchecked(Box::from_raw(raw as *mut T))
+    }
+}
+
 // pub type Identity<'hrtb, T> = <T as IdentityIgnoring<'hrtb>>::ItSelf;
 // // where
 // pub trait IdentityIgnoring<'__> {
@@ -1,15 +1,18 @@
-use crate::{CustomWrapper, Projectable, ProjectableMarker};
+use crate::{
+    CustomWrapper, DerefChain, DerefProjectable, Duplicate, Projectable, ProjectableMarker,
+    SupportsEnums,
+};
 use core::cell::{Ref, RefMut};
 use core::mem;
 use core::mem::{transmute_copy, ManuallyDrop};
 use core::ops::Deref;
 use core::ptr::NonNull;
 
-unsafe impl<'a, T> CustomWrapper for Ref<'a, T> {
+unsafe impl<'a, T: ?Sized> CustomWrapper for Ref<'a, T> {
     type Output = Ref<'a, T>;
 }
 
-unsafe impl<'a, T> Projectable for Ref<'a, T> {
+unsafe impl<'a, T: ?Sized> Projectable for Ref<'a, T> {
     type Target = T;
     type Marker = RefCellMarker<Ref<'a, ()>>;
 
@@ -23,13 +26,27 @@ unsafe impl<'a, T> Projectable for Ref<'a, T> {
     }
 }
 
+// guard of a pointer, like `Ref<'_, Option<&T>>`, is dereferenced through both of them
+unsafe impl<'a, T: DerefChain<RefCellMarker<Ref<'a, ()>>>> DerefProjectable for Ref<'a, T> {
+    type Target = T::Target;
+    type Marker = T::Marker;
+
+    fn deref_raw(&self) -> (*mut Self::Target, Self::Marker) {
+        let (raw, marker) = self.get_raw();
+        unsafe { T::deref_chain(raw, marker) }
+    }
+}
+
 #[repr(transparent)]
 pub struct RefCellMarker<T>(T);
 impl<T> RefCellMarker<T> {
+    #[deprecated(note = "`project!` no longer requires markers to have a `check` method")]
     pub fn check(&self) {}
 }
+unsafe impl<'a> SupportsEnums for RefCellMarker<Ref<'a, ()>> {}
+unsafe impl<'a> SupportsEnums for RefCellMarker<RefMut<'a, ()>> {}
 
-impl<'a, T: 'a> ProjectableMarker<T> for RefCellMarker<Ref<'a, ()>> {
+impl<'a, T: ?Sized + 'a> ProjectableMarker<T> for RefCellMarker<Ref<'a, ()>> {
     type Output = Ref<'a, T>;
 
     unsafe fn from_raw(&self, raw: *mut T) -> Self::Output {
@@ -37,11 +54,11 @@ impl<'a, T: 'a> ProjectableMarker<T> for RefCellMarker<Ref<'a, ()>> {
     }
 }
 
-unsafe impl<'a, T> CustomWrapper for RefMut<'a, T> {
+unsafe impl<'a, T: ?Sized> CustomWrapper for RefMut<'a, T> {
     type Output = RefMut<'a, T>;
 }
 
-unsafe impl<'a, T> Projectable for RefMut<'a, T> {
+unsafe impl<'a, T: ?Sized> Projectable for RefMut<'a, T> {
     type Target = T;
     type Marker = RefCellMarker<RefMut<'a, ()>>;
 
@@ -63,7 +80,17 @@ unsafe impl<'a, T> Projectable for RefMut<'a, T> {
     }
 }
 
-impl<'a, T: 'a> ProjectableMarker<T> for RefCellMarker<RefMut<'a, ()>> {
+unsafe impl<'a, T: DerefChain<RefCellMarker<RefMut<'a, ()>>>> DerefProjectable for RefMut<'a, T> {
+    type Target = T::Target;
+    type Marker = T::Marker;
+
+    fn deref_raw(&self) -> (*mut Self::Target, Self::Marker) {
+        let (raw, marker) = self.get_raw();
+        unsafe { T::deref_chain(raw, marker) }
+    }
+}
+
+impl<'a, T: ?Sized + 'a> ProjectableMarker<T> for RefCellMarker<RefMut<'a, ()>> {
     type Output = RefMut<'a, T>;
 
     unsafe fn from_raw(&self, raw: *mut T) -> Self::Output {
@@ -74,3 +101,9 @@ impl<'a, T: 'a> ProjectableMarker<T> for RefCellMarker<RefMut<'a, ()>> {
         out
     }
 }
+
+impl<'a, T: ?Sized> Duplicate for Ref<'a, T> {
+    fn duplicate(&self) -> Self {
+        Ref::clone(self)
+    }
+}
@@ -0,0 +1,151 @@
+//! Measures compile time of destructuring structs with many fields.
+//!
+//! For every struct size a small crate is generated that destructures a struct, a struct with a nested
+//! pattern for every field and a tuple struct with `project!`, and the time of `cargo check` for that
+//! crate is reported.
+//! Run with `cargo run --release --manifest-path tests/compile_time/Cargo.toml`.
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const SIZES: &[usize] = &[10, 100, 500];
+
+fn main() {
+    let projecture = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let projecture = projecture.canonicalize().expect("projecture directory");
+    let work_dir = projecture.join("target").join("compile_time");
+    let target_dir = work_dir.join("target");
+
+    println!(
+        "{:>8} {:>12} {:>12} {:>12}",
+        "fields", "struct", "nested", "tuple"
+    );
+    for &size in SIZES {
+        let named = measure(
+            &work_dir,
+            &target_dir,
+            &projecture,
+            "named",
+            size,
+            named_source(size),
+        );
+        let nested = measure(
+            &work_dir,
+            &target_dir,
+            &projecture,
+            "nested",
+            size,
+            nested_source(size),
+        );
+        let tuple = measure(
+            &work_dir,
+            &target_dir,
+            &projecture,
+            "tuple",
+            size,
+            tuple_source(size),
+        );
+        println!("{:>8} {:>12?} {:>12?} {:>12?}", size, named, nested, tuple);
+    }
+}
+
+// time of `cargo check` for a generated crate, dependencies are checked beforehand
+fn measure(
+    work_dir: &Path,
+    target_dir: &Path,
+    projecture: &Path,
+    kind: &str,
+    size: usize,
+    source: String,
+) -> Duration {
+    let name = format!("{}_{}", kind, size);
+    let dir = work_dir.join(&name);
+    fs::create_dir_all(dir.join("src")).unwrap();
+    let manifest = format!(
+        "[package]\nname = \"{}\"\nversion = \"0.0.0\"\nedition = \"2018\"\n\n[dependencies]\nprojecture = {{ path = {:?} }}\n\n[workspace]\n",
+        name, projecture
+    );
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    fs::write(dir.join("src/lib.rs"), "").unwrap();
+    check(&dir, target_dir);
+
+    fs::write(dir.join("src/lib.rs"), source).unwrap();
+    let start = Instant::now();
+    check(&dir, target_dir);
+    start.elapsed()
+}
+
+fn check(dir: &Path, target_dir: &Path) {
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let status = Command::new(cargo)
+        .arg("check")
+        .arg("--quiet")
+        .arg("--manifest-path")
+        .arg(dir.join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", target_dir)
+        .env("RUSTFLAGS", "--cap-lints=allow")
+        .status()
+        .expect("failed to run cargo");
+    assert!(
+        status.success(),
+        "failed to compile {}",
+        PathBuf::from(dir).display()
+    );
+}
+
+fn named_source(size: usize) -> String {
+    let mut fields = String::new();
+    let mut bindings = String::new();
+    for i in 0..size {
+        writeln!(fields, "    pub field{}: u32,", i).unwrap();
+        write!(bindings, "field{}, ", i).unwrap();
+    }
+    format!(
+        "use projecture::project;\n\n\
+         pub struct Wide {{\n{}}}\n\n\
+         pub fn first_and_last(wide: &mut Wide) -> (&mut u32, &mut u32) {{\n    \
+         project!(let Wide {{ {}}} = wide);\n    \
+         (field0, field{})\n}}\n",
+        fields,
+        bindings,
+        size - 1
+    )
+}
+
+fn nested_source(size: usize) -> String {
+    let mut fields = String::new();
+    let mut bindings = String::new();
+    for i in 0..size {
+        writeln!(fields, "    pub field{}: Inner,", i).unwrap();
+        write!(bindings, "field{0}: Inner {{ value: value{0}, .. }}, ", i).unwrap();
+    }
+    format!(
+        "use projecture::project;\n\n\
+         pub struct Inner {{\n    pub value: u32,\n    pub other: u32,\n}}\n\n\
+         pub struct Wide {{\n{}}}\n\n\
+         pub fn first_and_last(wide: &mut Wide) -> (&mut u32, &mut u32) {{\n    \
+         project!(let Wide {{ {}}} = wide);\n    \
+         (value0, value{})\n}}\n",
+        fields,
+        bindings,
+        size - 1
+    )
+}
+
+fn tuple_source(size: usize) -> String {
+    let fields = vec!["pub u32"; size].join(", ");
+    let mut pattern = vec!["_"; size];
+    pattern[0] = "first";
+    pattern[size - 1] = "last";
+    format!(
+        "use projecture::project;\n\n\
+         pub struct Wide({});\n\n\
+         pub fn first_and_last(wide: &mut Wide) -> (&mut u32, &mut u32) {{\n    \
+         project!(let Wide({}) = wide);\n    \
+         (first, last)\n}}\n",
+        fields,
+        pattern.join(", ")
+    )
+}
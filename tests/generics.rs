@@ -0,0 +1,202 @@
+// Struct shapes that derive macros have to parse, each one is used with all of them
+#![cfg(feature = "macro_rules_attribute")]
+#![allow(dead_code)]
+use macro_rules_attribute::{apply, derive};
+use projecture::pin::{PinDrop, Unpinned};
+use projecture::{pin_projectable, project, CallGuard, FieldOf, Fields, PinFields, PinProjectable};
+use std::fmt::Debug;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+fn assert_unpin<T: Unpin>() {}
+
+#[derive(PinProjectable!, Fields!, FieldOf!)]
+struct Unit;
+
+#[derive(PinProjectable!, Fields!, FieldOf!)]
+struct Empty {}
+
+#[derive(PinProjectable!, Fields!, FieldOf!)]
+struct Lifetimes<'a, 'b: 'a, T: ?Sized + 'a>(&'a &'b T, Unpinned<usize>);
+
+#[derive(PinProjectable!, Fields!, FieldOf!)]
+struct Const<T, const N: usize> {
+    items: [T; N],
+}
+
+#[derive(PinProjectable!, Fields!, FieldOf!)]
+struct Defaults<T = u8, U: Default = Vec<Vec<u8>>> {
+    items: [T; 3],
+    rest: Unpinned<U>,
+}
+
+#[derive(PinProjectable!, Fields!, FieldOf!)]
+struct Nested<T: Iterator<Item = Vec<Option<u8>>>, F: for<'a> Fn(&'a u8) -> Option<&'a u8>> {
+    iter: T,
+    f: F,
+}
+
+#[derive(PinProjectable!, Fields!, FieldOf!)]
+struct TupleWhere<T>(T, Unpinned<usize>)
+where
+    T: Into<Vec<u8>>;
+
+#[derive(PinProjectable!, Fields!, FieldOf!)]
+struct Where<'a, T: 'a + ?Sized>
+where
+    for<'b> &'b T: Debug,
+    T: Debug,
+{
+    value: &'a T,
+}
+
+#[derive(PinProjectable!, Fields!)]
+enum Enum<T = u8>
+where
+    T: Copy,
+{
+    Items([T; 2]),
+    Empty,
+}
+
+pin_projectable!(Manual<'a, T: ?Sized + 'a, const N: usize> where T: Debug);
+struct Manual<'a, T: ?Sized + 'a, const N: usize>(&'a T, [u8; N])
+where
+    T: Debug;
+
+mod pin_fields {
+    use super::*;
+
+    #[apply(PinFields!)]
+    pub struct Const<T, const N: usize> {
+        #[pin]
+        pub items: [T; N],
+        pub count: usize,
+    }
+
+    #[apply(PinFields!)]
+    pub struct Defaults<T: Iterator<Item = Vec<u8>> = std::vec::IntoIter<Vec<u8>>, U = PhantomPinned>(
+        #[pin] pub T,
+        pub U,
+    )
+    where
+        U: Default;
+
+    #[apply(PinFields!)]
+    pub struct Where<'a, T: ?Sized>
+    where
+        for<'b> &'b T: Debug,
+    {
+        pub value: &'a T,
+        #[pin]
+        pub pinned: PhantomPinned,
+    }
+}
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+impl<T, U: Default> PinDrop for Defaults<T, U> {
+    fn drop(_this: CallGuard<Pin<&mut Self>>) {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+impl<T, const N: usize> PinDrop for pin_fields::Const<T, N> {
+    fn drop(this: CallGuard<Pin<&mut Self>>) {
+        let this = this.into_inner();
+        *project!(this -> count) += 1;
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_generics_projection() {
+    let value = 1;
+    let value_ref = &value;
+    let mut lifetimes = Box::pin(Lifetimes(&value_ref, Unpinned(2)));
+    project!(let Lifetimes(_, count) = lifetimes.as_mut());
+    let _: &mut usize = count;
+
+    let mut items = Box::pin(Const {
+        items: [PhantomPinned; 2],
+    });
+    let items = items.as_mut();
+    let _: Pin<&mut PhantomPinned> = project!(items -> items -> [1]);
+
+    let mut nested = Box::pin(Nested {
+        iter: vec![vec![Some(1)]].into_iter(),
+        f: |x: &u8| Some(x),
+    });
+    project!(let Nested { iter, f } = nested.as_mut());
+    let _: &mut std::vec::IntoIter<Vec<Option<u8>>> = iter;
+    let _: &mut _ = f;
+
+    let mut tuple = Box::pin(TupleWhere("a", Unpinned(1)));
+    project!(let TupleWhere(value, count) = tuple.as_mut());
+    let _: &mut &str = value;
+    *count += 1;
+    assert_eq!(*tuple.1, 2);
+
+    let mut value = Box::pin(Where { value: "a" });
+    let value = value.as_mut();
+    let _: &mut &str = project!(value -> value);
+
+    let mut value: Pin<Box<Enum>> = Box::pin(Enum::Items([1, 2]));
+    project!(if let Enum::Items(items) = value.as_mut() {
+        let _: &mut [u8; 2] = items;
+    } else {
+        unreachable!()
+    });
+
+    let manual = Box::pin(Manual(&1, [0; 3]));
+    let manual = manual.as_ref();
+    let _: &[u8; 3] = project!(manual -> 1);
+}
+
+#[test]
+fn test_generics_drop() {
+    let defaults: Defaults = Defaults {
+        items: [1; 3],
+        rest: Unpinned(vec![]),
+    };
+    drop(defaults);
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 1);
+
+    let mut boxed = Box::pin(pin_fields::Const {
+        items: [PhantomPinned],
+        count: 0,
+    });
+    project!(let pin_fields::Const { items, count } = boxed.as_mut());
+    let _: Pin<&mut [PhantomPinned; 1]> = items;
+    let _: &mut usize = count;
+    drop(boxed);
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 2);
+
+    // owned destructuring drops the rest with `Fields!`
+    let defaults: Defaults<u16, Vec<u8>> = Defaults {
+        items: [1; 3],
+        rest: Unpinned(vec![1]),
+    };
+    project!(let Defaults { items, .. } = defaults);
+    assert_eq!(items, [1; 3]);
+}
+
+#[test]
+fn test_generics_unpin() {
+    assert_unpin::<pin_fields::Const<u8, 2>>();
+    assert_unpin::<pin_fields::Defaults<std::vec::IntoIter<Vec<u8>>, PhantomPinned>>();
+
+    let mut value = Box::pin(pin_fields::Defaults(
+        vec![vec![1]].into_iter(),
+        PhantomPinned,
+    ));
+    project!(let pin_fields::Defaults(iter, pinned) = value.as_mut());
+    let _: &mut std::vec::IntoIter<Vec<u8>> = iter;
+    let _: &mut PhantomPinned = pinned;
+
+    let value = Box::pin(pin_fields::Where {
+        value: "a",
+        pinned: PhantomPinned,
+    });
+    let value = value.as_ref();
+    let _: &&str = project!(value -> value);
+}
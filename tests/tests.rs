@@ -1,8 +1,9 @@
 // #![feature(arbitrary_self_types)]
-use projecture::{pin_projectable, project, OptionMarker};
+use projecture::{pin_projectable, project, Projectable, ProjectableMarker};
 use std::cell::Cell;
 use std::fmt::Debug;
 use std::marker::PhantomPinned;
+use std::mem::ManuallyDrop;
 use std::pin::Pin;
 use std::ptr::addr_of_mut;
 
@@ -107,3 +108,1693 @@ fn test_tmp() {
     let x: Option<Box<usize>> = x;
     // let y: Option<usize> = y;
 }
+
+#[test]
+fn test_enum_drop() {
+    use std::rc::Rc;
+    struct Counter(Rc<Cell<usize>>);
+    impl Drop for Counter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+    enum Foo {
+        A(Counter, Counter),
+        B(Counter),
+    }
+    impl Drop for Foo {
+        fn drop(&mut self) {
+            panic!("destructured enum must not be dropped")
+        }
+    }
+
+    let drops = Rc::new(Cell::new(0));
+    let foo = Foo::A(Counter(drops.clone()), Counter(drops.clone()));
+    project!(match foo {
+        Foo::A(x, y) => {
+            drop(x);
+            assert_eq!(drops.get(), 1);
+            drop(y);
+        }
+        Foo::B(_) => unreachable!(),
+    });
+    assert_eq!(drops.get(), 2);
+
+    let foo = Foo::B(Counter(drops.clone()));
+    project!(let Foo::B(x) = foo else { unreachable!() });
+    drop(x);
+    assert_eq!(drops.get(), 3);
+}
+
+#[cfg(feature = "macro_rules_attribute")]
+#[test]
+fn test_drop_unmentioned_fields() {
+    use macro_rules_attribute::derive;
+    use projecture::Fields;
+    use std::rc::Rc;
+    #[derive(Clone)]
+    struct Counter(Rc<Cell<usize>>);
+    impl Drop for Counter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+    #[derive(Fields!)]
+    pub struct Foo<T: Clone> {
+        x: T,
+        y: Counter,
+        z: Box<Bar>,
+    }
+    #[derive(Fields!)]
+    #[repr(packed)]
+    struct Bar(u8, Counter, Counter);
+    #[derive(Fields!)]
+    enum Baz {
+        A { x: Counter, y: Counter },
+        B(Counter, Counter, Counter),
+    }
+    impl<T: Clone> Drop for Foo<T> {
+        fn drop(&mut self) {
+            panic!("destructured struct must not be dropped")
+        }
+    }
+    impl Drop for Bar {
+        fn drop(&mut self) {
+            panic!("destructured struct must not be dropped")
+        }
+    }
+    impl Drop for Baz {
+        fn drop(&mut self) {
+            panic!("destructured enum must not be dropped")
+        }
+    }
+
+    let drops = Rc::new(Cell::new(0));
+    let c = Counter(drops.clone());
+    let foo = Foo {
+        x: c.clone(),
+        y: c.clone(),
+        z: Box::new(Bar(1, c.clone(), c.clone())),
+    };
+    project!(let Foo { x, z: *Bar(a, ..), .. } = foo);
+    assert_eq!(drops.get(), 3);
+    assert_eq!(a, 1);
+    drop(x);
+    assert_eq!(drops.get(), 4);
+
+    let baz = Baz::A {
+        x: c.clone(),
+        y: c.clone(),
+    };
+    project!(let Baz::A { y, .. } = baz else { unreachable!() });
+    assert_eq!(drops.get(), 5);
+    drop(y);
+
+    let baz = Baz::B(c.clone(), c.clone(), c.clone());
+    project!(match baz {
+        Baz::B(_, x, ..) => {
+            assert_eq!(drops.get(), 8);
+            drop(x);
+        }
+        _ => unreachable!(),
+    });
+    assert_eq!(drops.get(), 9);
+
+    // non owned projections are not affected
+    let mut foo = Foo {
+        x: 1,
+        y: c.clone(),
+        z: Box::new(Bar(1, c.clone(), c.clone())),
+    };
+    project!(let Foo { x, .. } = &mut foo);
+    *x = 2;
+    assert_eq!(drops.get(), 9);
+    project!(let Foo { z: *Bar(..), .. } = foo);
+    assert_eq!(drops.get(), 12);
+}
+
+#[test]
+fn test_method_chain() {
+    struct Foo {
+        fut: Inner,
+        other: Option<Box<Inner>>,
+    }
+    struct Inner {
+        count: usize,
+        _p: PhantomPinned,
+    }
+    impl Inner {
+        fn poll(self: Pin<&mut Self>, cx: &mut usize) -> usize {
+            let count = project!(self -> count);
+            *count += *cx;
+            *count
+        }
+    }
+    pin_projectable! { Foo }
+    pin_projectable! { Inner }
+
+    let mut foo = Box::pin(Foo {
+        fut: Inner {
+            count: 0,
+            _p: PhantomPinned,
+        },
+        other: Some(Box::new(Inner {
+            count: 1,
+            _p: PhantomPinned,
+        })),
+    });
+    let mut cx = 2;
+    assert_eq!(project!((foo.as_mut()) -> fut -> poll(&mut cx)), 2);
+    assert_eq!(project!((foo.as_mut()) -> fut -> poll(&mut cx)), 4);
+
+    let count: Option<&mut usize> = project!((foo.as_mut()) -> other -> as_mut() -> count);
+    *count.unwrap() += 1;
+    assert_eq!(
+        project!((foo.as_ref()) -> other -> as_ref() -> count),
+        Some(&2)
+    );
+}
+
+#[test]
+fn test_patterns() {
+    use std::cell::RefCell;
+    #[derive(Clone, Copy)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    struct Tuple(u8, String, Point, Box<u32>);
+    enum Foo {
+        A { tag: u8, point: Point },
+        B(Tuple),
+        C(Option<usize>, u8),
+    }
+
+    fn classify(foo: &Foo) -> i32 {
+        project!(match foo {
+            Foo::A { tag: 0, point: &Point { x, y } } => x + y,
+            Foo::A { tag: 1..=5, point: p @ Point { x, .. } } => {
+                let p: &Point = p;
+                p.y * x
+            }
+            Foo::A { point: Point { x: -1, .. }, .. } => -4,
+            Foo::A { point: Point { y: -5..=-2, .. }, .. } => -5,
+            Foo::A { tag: 0..=255, .. } => -1,
+            Foo::B(Tuple(7, s, ..)) => s.len() as i32,
+            Foo::B(Tuple(_, .., *last)) => *last as i32,
+            Foo::C(Option::Some(3), x) => *x as i32,
+            Foo::C(Option::None, _) => -2,
+            _ => -3,
+        })
+    }
+    let point = Point { x: 2, y: 3 };
+    assert_eq!(classify(&Foo::A { tag: 0, point }), 5);
+    assert_eq!(classify(&Foo::A { tag: 3, point }), 6);
+    assert_eq!(classify(&Foo::A { tag: 6, point }), -1);
+    let point2 = Point { x: -1, y: -3 };
+    assert_eq!(
+        classify(&Foo::A {
+            tag: 6,
+            point: point2
+        }),
+        -4
+    );
+    let point2 = Point { x: 1, y: -3 };
+    assert_eq!(
+        classify(&Foo::A {
+            tag: 6,
+            point: point2
+        }),
+        -5
+    );
+    let tuple = |a| Tuple(a, "abc".to_string(), point, Box::new(4));
+    assert_eq!(classify(&Foo::B(tuple(7))), 3);
+    assert_eq!(classify(&Foo::B(tuple(8))), 4);
+    assert_eq!(classify(&Foo::C(Some(3), 9)), 9);
+    assert_eq!(classify(&Foo::C(None, 9)), -2);
+    assert_eq!(classify(&Foo::C(Some(4), 9)), -3);
+
+    // `..` in the middle of tuple struct patterns
+    let mut t = tuple(1);
+    project!(let Tuple(a, .., Point { y, .. }, last) = &mut t);
+    *a += 1;
+    *y += 1;
+    **last += 1;
+    assert_eq!((t.0, t.2.y, *t.3), (2, 4, 5));
+    project!(let Tuple(.., s, &Point { x, .. }, _) = &t);
+    let s: &String = s;
+    let x: i32 = x;
+    assert_eq!((s.as_str(), x), ("abc", 2));
+
+    // owned, `Tuple` is not `Fields`, so skipped fields are leaked and `..` covers only `Copy` ones
+    project!(let Tuple(a, s, .., last) = tuple(1));
+    assert_eq!((a, s.as_str(), *last), (1, "abc", 4));
+
+    // `@` bindings
+    let cell = RefCell::new(tuple(1));
+    project!(let whole @ Tuple(_, s, ..) = cell.borrow());
+    assert_eq!(whole.1, *s);
+
+    if let Foo::A { .. } = Foo::C(None, 1) {
+        unreachable!()
+    }
+    let foo = Foo::C(Some(1), 2);
+    project!(let Foo::C(Option::Some(1), x) = &foo else { unreachable!() });
+    assert_eq!(*x, 2);
+    project!(if let Foo::C(Option::Some(2), _) = &foo {
+        unreachable!()
+    });
+
+    // variant is read only before the fields are borrowed mutably, even if its niche is in a field
+    enum Niche {
+        A(Box<u32>, u32),
+        B { first: Box<u32>, second: u32 },
+        C,
+    }
+    let mut niche = Niche::A(Box::new(1), 2);
+    project!(match &mut niche {
+        Niche::A(first, second) => {
+            **first += 1;
+            *second += 1;
+        }
+        _ => unreachable!(),
+    });
+    project!(let Niche::A(first, second) = &mut niche else { unreachable!() });
+    assert_eq!((**first, *second), (2, 3));
+    let mut niche = Niche::B {
+        first: Box::new(1),
+        second: 2,
+    };
+    project!(let Niche::B { first, second } = &mut niche else { unreachable!() });
+    **first += 1;
+    *second += 1;
+    assert!(matches!(niche, Niche::B { first, second: 3 } if *first == 2));
+    assert!(!matches!(Niche::C, Niche::A(..) | Niche::B { .. }));
+}
+
+#[test]
+fn test_index() {
+    use std::cell::RefCell;
+    use std::mem::MaybeUninit;
+    use std::ptr::NonNull;
+
+    struct Foo {
+        items: [Point; 3],
+        name: String,
+    }
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    let point = |x| Point { x, y: 0 };
+    let mut foo = Foo {
+        items: [point(1), point(2), point(3)],
+        name: String::new(),
+    };
+
+    *project!((&mut foo) -> items -> [1] -> y) = 5;
+    let slice: &mut [Point] = project!((&mut foo) -> items -> [1..]);
+    slice[1].y = 6;
+    assert_eq!(project!((&foo) -> items -> [..=1]).len(), 2);
+    assert_eq!(project!((&foo) -> items -> [..]).len(), 3);
+    let i = 2;
+    assert_eq!(*project!((&foo) -> items -> [i] -> y), 6);
+    assert_eq!(
+        *project!((&foo) -> items -> [..2] -> [1]),
+        Point { x: 2, y: 5 }
+    );
+    project!((&mut foo) -> name).push('a');
+
+    // through `Deref`
+    let mut vec = vec![point(1), point(2)];
+    *project!((&mut vec) -> [1] -> x) = 3;
+    assert_eq!(vec[1], point(3));
+
+    let cell = Cell::new([1, 2, 3]);
+    let cells: &Cell<[i32]> = project!((&cell) -> [1..]);
+    cells.as_slice_of_cells()[0].set(4);
+    assert_eq!(cell.get(), [1, 4, 3]);
+
+    let mut mu = MaybeUninit::<[Point; 3]>::uninit();
+    let first: &mut MaybeUninit<Point> = project!((&mut mu) -> [0]);
+    first.write(point(0));
+    let rest: &mut [MaybeUninit<Point>] = project!((&mut mu) -> [1..]);
+    for (i, item) in rest.iter_mut().enumerate() {
+        item.write(point(i as i32 + 1));
+    }
+    assert_eq!(unsafe { mu.assume_init() }, [point(0), point(1), point(2)]);
+
+    let refcell = RefCell::new(foo);
+    *project!((refcell.borrow_mut()) -> items -> [0] -> x) = 7;
+    let item = project!((refcell.borrow()) -> items -> [0]);
+    assert_eq!(*item, Point { x: 7, y: 0 });
+    drop(item);
+
+    let mut pinned = Box::pin([point(1), point(2)]);
+    let y: &mut i32 = project!((pinned.as_mut()) -> [1] -> y);
+    *y = 1;
+    assert_eq!(pinned[1], Point { x: 2, y: 1 });
+
+    // raw pointers wrap instead of panicking
+    let mut array = [1u8, 2, 3];
+    let ptr = &mut array as *mut [u8; 3];
+    assert_eq!(project!(ptr -> [3]), (ptr as *mut u8).wrapping_add(3));
+    assert_eq!(unsafe { *project!(ptr -> [2]) }, 3);
+    let ptr = &mut array[..] as *mut [u8];
+    let tail: *mut [u8] = project!(ptr -> [1..]);
+    assert_eq!(unsafe { &*tail }, &[2, 3]);
+    let ptr = NonNull::new(&mut array as *mut [u8; 3]);
+    let item: Option<NonNull<u8>> = project!(ptr -> [10]);
+    assert!(item.is_some());
+}
+
+#[test]
+#[should_panic(expected = "index out of bounds: the len is 3 but the index is 3")]
+fn test_index_out_of_bounds() {
+    let array = [1, 2, 3];
+    let i = 3;
+    project!((&array) -> [i]);
+}
+
+#[test]
+#[should_panic(expected = "range end index 4 out of range for slice of length 3")]
+fn test_range_out_of_bounds() {
+    let vec = vec![1, 2, 3];
+    project!((&vec) -> [1..4]);
+}
+
+#[cfg(feature = "atomic")]
+#[test]
+fn test_index_atomic() {
+    use atomic::Atomic;
+    use std::sync::atomic::Ordering;
+
+    let atomic = Atomic::new([0u32; 4]);
+    project!((&atomic) -> [2]).store(1, Ordering::Relaxed);
+    let values: Vec<u32> = (0..4)
+        .map(|i| project!((&atomic) -> [i]).load(Ordering::Relaxed))
+        .collect();
+    assert_eq!(values, [0, 0, 1, 0]);
+
+    let maybe_atomic = Some(&atomic);
+    let item: Option<&Atomic<u32>> = project!(maybe_atomic -> [3]);
+    item.unwrap().store(2, Ordering::Relaxed);
+    assert_eq!(project!((&atomic) -> [3]).load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_array_patterns() {
+    use std::mem::MaybeUninit;
+    use std::rc::Rc;
+
+    struct Foo {
+        buf: [Point; 4],
+        name: String,
+    }
+    #[derive(Debug, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    let point = |x| Point { x, y: 0 };
+    let mut foo = Foo {
+        buf: [point(1), point(2), point(3), point(4)],
+        name: "foo".to_string(),
+    };
+
+    project!(let Foo { buf: [Point { x, .. }, second, rest @ ..], name } = &mut foo);
+    let rest: &mut [Point] = rest;
+    *x += 10;
+    second.y = 1;
+    rest[1].y = 2;
+    name.push('!');
+    assert_eq!(
+        foo.buf,
+        [
+            point(11),
+            Point { x: 2, y: 1 },
+            point(3),
+            Point { x: 4, y: 2 }
+        ]
+    );
+
+    project!(let [.., third, _] = &foo.buf);
+    assert_eq!(third, &point(3));
+    project!(let [rest @ .., last] = &foo.buf);
+    assert_eq!((rest.len(), last.x), (3, 4));
+
+    let cell = Cell::new([1, 2, 3]);
+    project!(let [a, b, c] = &cell);
+    let a: &Cell<i32> = a;
+    a.set(b.get() + c.get());
+    assert_eq!(cell.get(), [5, 2, 3]);
+
+    let mut mu = MaybeUninit::<[u8; 3]>::uninit();
+    project!(let [first, rest @ ..] = &mut mu);
+    let rest: &mut [MaybeUninit<u8>] = rest;
+    first.write(1);
+    rest[0].write(2);
+    rest[1].write(3);
+    assert_eq!(unsafe { mu.assume_init() }, [1, 2, 3]);
+
+    let mut pinned = Box::pin([PhantomPinned, PhantomPinned]);
+    project!(let [x, rest @ ..] = pinned.as_mut());
+    let _: Pin<&mut PhantomPinned> = x;
+    let _: Pin<&mut [PhantomPinned]> = rest;
+
+    // unmentioned items of owned arrays are dropped
+    let rc = Rc::new(());
+    project!(let [a, .., b] = [rc.clone(), rc.clone(), rc.clone(), rc.clone()]);
+    assert_eq!(Rc::strong_count(&rc), 3);
+    drop((a, b));
+    assert_eq!(Rc::strong_count(&rc), 1);
+
+    enum Bar {
+        A([i32; 3]),
+        B,
+    }
+    let classify = |bar: &Bar| {
+        project!(match bar {
+            Bar::A([0, ..]) => 0,
+            Bar::A([_, 1..=5, last]) => *last,
+            Bar::A([rest @ .., _]) => rest.iter().sum(),
+            Bar::B => -1,
+        })
+    };
+    assert_eq!(classify(&Bar::A([0, 1, 2])), 0);
+    assert_eq!(classify(&Bar::A([1, 2, 3])), 3);
+    assert_eq!(classify(&Bar::A([1, 7, 3])), 8);
+    assert_eq!(classify(&Bar::B), -1);
+}
+
+mod net {
+    pub struct Header {
+        pub len: usize,
+        pub kind: u8,
+    }
+    pub struct Wrapper<T>(pub T, pub usize);
+    pub mod other {
+        pub struct Header {
+            pub len: u32,
+        }
+    }
+}
+
+impl<T> net::Wrapper<T> {
+    fn parts(&self) -> (&T, &usize) {
+        project!(let Self(a, b) = self);
+        (a, b)
+    }
+}
+
+#[test]
+fn test_struct_paths() {
+    struct Wrapper<T> {
+        inner: T,
+    }
+    impl<T> Wrapper<T> {
+        fn inner(&mut self) -> &mut T {
+            project!(let Self { inner } = self);
+            inner
+        }
+    }
+    let mut header = net::Header { len: 1, kind: 2 };
+    project!(let crate::net::Header { len, .. } = &mut header);
+    *len += 1;
+    project!(let net::Header { kind: k, len: l } = &header);
+    assert_eq!((*k, *l), (2, 2));
+    let other = Cell::new(net::other::Header { len: 3 });
+    project!(let self::net::other::Header { len } = &other);
+    let len: &Cell<u32> = len;
+    len.set(4);
+    assert_eq!(other.into_inner().len, 4);
+
+    let mut w = Wrapper { inner: 1u8 };
+    project!(let Wrapper::<u8> { inner } = &mut w);
+    *inner += 1;
+    *w.inner() += 1;
+    assert_eq!(w.inner, 3);
+
+    let t = net::Wrapper(String::from("a"), 5);
+    project!(let net::Wrapper::<String>(s, ..) = &t);
+    assert_eq!(s, "a");
+    assert_eq!(t.parts(), (&String::from("a"), &5));
+    project!(let net::Wrapper::<String>(s, n) = t);
+    assert_eq!((s, n), (String::from("a"), 5));
+
+    // nested and inside enum arms
+    struct Outer {
+        header: net::Header,
+    }
+    enum Packet {
+        Data(net::Header, Wrapper<u8>),
+        Empty,
+    }
+    let outer = Outer {
+        header: net::Header { len: 7, kind: 0 },
+    };
+    project!(let Outer { header: net::Header { len, .. } } = &outer);
+    assert_eq!(*len, 7);
+    let packet = Packet::Data(net::Header { len: 8, kind: 1 }, Wrapper { inner: 9 });
+    let sum = |packet: &Packet| {
+        project!(match packet {
+            Packet::Data(net::Header { kind: 0, .. }, _) => 0,
+            Packet::Data(net::Header { len, .. }, Wrapper::<u8> { inner }) =>
+                *len + *inner as usize,
+            Packet::Empty => 1,
+        })
+    };
+    assert_eq!(sum(&packet), 17);
+    assert_eq!(sum(&Packet::Empty), 1);
+}
+
+#[test]
+fn test_fields_tuple() {
+    use std::ptr::NonNull;
+
+    struct Foo {
+        a: usize,
+        b: String,
+        c: Box<Point>,
+    }
+    #[derive(Debug, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    fn split(foo: &mut Foo) -> (&mut usize, &mut String, &mut Point) {
+        project!(foo -> {a, b, c: *inner})
+    }
+    let mut foo = Foo {
+        a: 1,
+        b: String::new(),
+        c: Box::new(Point { x: 0, y: 0 }),
+    };
+    let (a, b, c) = split(&mut foo);
+    *a += 1;
+    b.push('b');
+    c.x = 3;
+    assert_eq!(
+        (foo.a, foo.b.as_str(), &*foo.c),
+        (2, "b", &Point { x: 3, y: 0 })
+    );
+
+    let cell = Cell::new(Point { x: 1, y: 2 });
+    let swap = |cell: &Cell<Point>| {
+        let (x, y) = project!(cell -> {x, y});
+        x.swap(y);
+    };
+    swap(&cell);
+    assert_eq!(cell.into_inner(), Point { x: 2, y: 1 });
+
+    let (first,) = project!((&foo) -> {b});
+    assert_eq!(first, "b");
+
+    #[repr(C, packed)]
+    struct Packed(u8, u32, u16);
+    let mut packed = Packed(1, 2, 3);
+    let ptr = NonNull::from(&mut packed);
+    let (first, last) = project!(ptr -> {0, 2: last});
+    let values = unsafe { (first.as_ptr().read(), last.as_ptr().read_unaligned()) };
+    assert_eq!(values, (1, 3));
+}
+
+#[test]
+fn test_optional_chaining() {
+    use std::cell::RefCell;
+
+    struct Node {
+        left: Option<Box<Node>>,
+        right: Option<Box<Node>>,
+        value: u32,
+    }
+    impl Node {
+        fn leaf(value: u32) -> Self {
+            Node {
+                left: None,
+                right: None,
+                value,
+            }
+        }
+    }
+    let mut root = Node {
+        left: Some(Box::new(Node {
+            right: Some(Box::new(Node::leaf(5))),
+            ..Node::leaf(1)
+        })),
+        ..Node::leaf(0)
+    };
+
+    let r = &mut root;
+    let value: Option<&mut u32> = project!(r -> left ? -> right ? -> value);
+    *value.unwrap() += 1;
+    let r = &mut root;
+    assert!(project!(r -> right ? -> left ? -> value).is_none());
+    let r = &mut root;
+    assert!(project!(r -> left ? -> left ? -> value).is_none());
+
+    let r = &root;
+    assert_eq!(project!(r -> left ? -> right ? -> value), Some(&6));
+    let o = Some(&root);
+    assert_eq!(project!(o -> left ? -> value), Some(&1));
+
+    struct Config {
+        limits: Option<(u32, u32)>,
+    }
+    let config = RefCell::new(Config {
+        limits: Some((1, 2)),
+    });
+    let r = config.borrow_mut();
+    let mut max = project!(r -> limits ? -> 1).unwrap();
+    *max = 3;
+    drop(max);
+    assert_eq!(config.borrow().limits, Some((1, 3)));
+}
+
+#[cfg(feature = "macro_rules_attribute")]
+#[test]
+fn test_generic() {
+    use std::cell::RefCell;
+    use std::future::Future;
+    use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    #[macro_rules_attribute::derive(projecture::PinProjectable!)]
+    struct Wrapper<T> {
+        inner: T,
+        items: [T; 2],
+        polls: projecture::Unpinned<usize>,
+        _p: PhantomPinned,
+    }
+    impl<T: Future> Future for Wrapper<T> {
+        type Output = T::Output;
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T::Output> {
+            project!(let Self { inner, polls, .. } = self);
+            *polls += 1;
+            inner.poll(cx)
+        }
+    }
+    impl<T> Wrapper<T> {
+        fn last(self: Pin<&mut Self>) -> Pin<&mut T> {
+            project!(self -> items -> [1])
+        }
+    }
+    fn unpin_items<T: Unpin>(x: Pin<&mut Wrapper<T>>) -> &mut [T] {
+        project!(x -> items -> [..])
+    }
+    fn last_cell<T, const N: usize>(x: &Cell<[T; N]>) -> &Cell<T> {
+        project!(x -> [N - 1])
+    }
+    enum Either<L, R> {
+        Left(L),
+        Right { value: R },
+    }
+    fn right<L, R>(x: &RefCell<Either<L, R>>) -> Option<std::cell::RefMut<'_, R>> {
+        project!(match x.borrow_mut() {
+            Either::Right { value } => Some(value),
+            _ => None,
+        })
+    }
+
+    let mut wrapper = Box::pin(Wrapper {
+        inner: std::future::ready(1),
+        items: [std::future::ready(2), std::future::ready(3)],
+        polls: projecture::Unpinned(0),
+        _p: PhantomPinned,
+    });
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(|_| RAW, |_| {}, |_| {}, |_| {});
+    const RAW: RawWaker = RawWaker::new(std::ptr::null(), &VTABLE);
+    let waker = unsafe { Waker::from_raw(RAW) };
+    let mut cx = Context::from_waker(&waker);
+    assert_eq!(wrapper.as_mut().poll(&mut cx), Poll::Ready(1));
+    assert_eq!(wrapper.as_mut().last().poll(&mut cx), Poll::Ready(3));
+    assert_eq!(*project!((wrapper.as_mut()) -> polls), 1);
+
+    let mut numbers = Box::pin(Wrapper {
+        inner: 0,
+        items: [1, 2],
+        polls: projecture::Unpinned(0),
+        _p: PhantomPinned,
+    });
+    unpin_items(numbers.as_mut())[0] = 5;
+    assert_eq!(numbers.items, [5, 2]);
+
+    let cell = Cell::new([1, 2, 3]);
+    last_cell(&cell).set(4);
+    assert_eq!(cell.get(), [1, 2, 4]);
+
+    let either = RefCell::new(Either::<u8, String>::Right {
+        value: String::new(),
+    });
+    right(&either).unwrap().push('a');
+    assert!(matches!(&*either.borrow(), Either::Right { value } if value == "a"));
+    *either.borrow_mut() = Either::Left(1);
+    assert!(right(&either).is_none());
+}
+
+#[cfg(feature = "macro_rules_attribute")]
+#[test]
+fn test_nested_pointer_kinds() {
+    use std::cell::{Ref, RefCell, RefMut};
+    use std::mem::MaybeUninit;
+    use std::ptr::NonNull;
+
+    #[macro_rules_attribute::derive(projecture::PinProjectable!)]
+    struct Foo {
+        a: usize,
+        u: projecture::Unpinned<usize>,
+        p: PhantomPinned,
+    }
+    impl Foo {
+        fn new() -> Self {
+            Foo {
+                a: 0,
+                u: projecture::Unpinned(0),
+                p: PhantomPinned,
+            }
+        }
+    }
+    let mut foo = Foo::new();
+
+    let x: Option<Pin<&mut Foo>> = Some(unsafe { Pin::new_unchecked(&mut foo) });
+    let (a, u): (Option<&mut usize>, Option<&mut usize>) = project!(x -> {a, u});
+    *a.unwrap() = 1;
+    *u.unwrap() = 2;
+    let x = Some(unsafe { Pin::new_unchecked(&mut foo) });
+    let p: Option<Pin<&mut PhantomPinned>> = project!(x -> p);
+    assert!(p.is_some());
+    let x: Option<Pin<&Foo>> = None;
+    assert_eq!(project!(x -> u), None);
+
+    let x: Option<Option<Option<&mut Foo>>> = Some(Some(Some(&mut foo)));
+    *project!(x -> a).unwrap() += 1;
+    let x: Option<Option<Option<&mut Foo>>> = Some(None);
+    assert_eq!(project!(x -> a), None);
+    assert_eq!((foo.a, foo.u.0), (2, 2));
+
+    let cell = RefCell::new(Foo::new());
+    let x = Some(unsafe { Pin::new_unchecked(cell.borrow_mut()) });
+    let a: Option<RefMut<usize>> = project!(x -> a);
+    *a.unwrap() = 3;
+    assert_eq!(cell.borrow().a, 3);
+
+    let cell = Cell::new((1, 2));
+    let x = Some(Some(&cell));
+    let second: Option<&Cell<i32>> = project!(x -> 1);
+    second.unwrap().set(3);
+    assert_eq!(cell.get(), (1, 3));
+
+    let mut mu = MaybeUninit::<(u8, u16)>::uninit();
+    let x = Some(Some(&mut mu));
+    let first: Option<&mut MaybeUninit<u8>> = project!(x -> 0);
+    first.unwrap().write(1);
+
+    let mut pair = (1, 2);
+    let x = Some(Some(NonNull::from(&mut pair)));
+    let second: Option<NonNull<i32>> = project!(x -> 1);
+    assert_eq!(unsafe { *second.unwrap().as_ptr() }, 2);
+
+    // pointers inside of the target of another pointer
+    struct T2 {
+        x: usize,
+    }
+    let mut t = T2 { x: 1 };
+    let cell = RefCell::new(Some(&mut t));
+    let o: Option<Pin<RefMut<'_, Option<&mut T2>>>> = Some(Pin::new(cell.borrow_mut()));
+    let x: Option<RefMut<usize>> = project!(o -> x);
+    *x.unwrap() += 1;
+    let o: Pin<RefMut<'_, Option<&mut T2>>> = Pin::new(cell.borrow_mut());
+    *project!(o -> x).unwrap() += 1;
+    let o = cell.borrow();
+    let x: Option<Ref<usize>> = project!(o -> x);
+    assert_eq!(*x.unwrap(), 3);
+    *cell.borrow_mut() = None;
+    let o: Option<Pin<RefMut<'_, Option<&mut T2>>>> = Some(Pin::new(cell.borrow_mut()));
+    assert!(project!(o -> x).is_none());
+    assert!(cell.try_borrow_mut().is_ok());
+
+    let cell = RefCell::new(Some(Some(unsafe { Pin::new_unchecked(&mut foo) })));
+    let o = cell.borrow_mut();
+    let (a, p): (Option<RefMut<usize>>, Option<Pin<RefMut<PhantomPinned>>>) = project!(o -> {a, p});
+    *a.unwrap() = 4;
+    assert!(p.is_some());
+    drop(p);
+    drop(cell);
+    assert_eq!(foo.a, 4);
+
+    let cell = RefCell::new(Box::new(T2 { x: 5 }));
+    let o = cell.borrow_mut();
+    *project!(o -> x) += 1;
+    assert_eq!(cell.borrow().x, 6);
+    let shared = T2 { x: 7 };
+    let cell = RefCell::new(&shared);
+    let o = cell.borrow_mut();
+    let x: &usize = project!(o -> x);
+    assert_eq!(*x, 7);
+}
+
+#[test]
+fn test_offset_of() {
+    use projecture::offset_of;
+    use std::mem::MaybeUninit;
+
+    #[repr(C)]
+    struct Foo {
+        a: u8,
+        b: Bar,
+        c: (u8, (u16, u32)),
+    }
+    #[repr(C)]
+    struct Bar(u16, u32);
+    #[repr(C, packed)]
+    struct Packed(u8, Bar);
+
+    assert_eq!(offset_of!(Foo, a), 0);
+    assert_eq!(offset_of!(Foo, b.1), 8);
+    assert_eq!(
+        offset_of!(Foo, c.1 .1),
+        offset_of!(Foo, c) + offset_of!((u8, (u16, u32)), 1.1)
+    );
+    assert_eq!(offset_of!(Packed, 1.1), 5);
+
+    let mut foo = MaybeUninit::<Foo>::uninit();
+    let ptr = foo.as_mut_ptr();
+    let field: *mut u32 = project!(ptr -> b -> 1);
+    assert_eq!(field as usize - ptr as usize, offset_of!(Foo, b.1));
+    // raw pointers may dangle, so their fields are found without projecting them in place
+    let ptr = std::ptr::NonNull::<Foo>::dangling().as_ptr();
+    let field: *mut u32 = project!(ptr -> b -> 1);
+    assert_eq!(field as usize - ptr as usize, offset_of!(Foo, b.1));
+    let field: *mut u16 = project!(ptr -> c -> 1 -> 0);
+    assert_eq!(field as usize - ptr as usize, offset_of!(Foo, c.1 .0));
+    project!(let Foo { c, a, b: Bar(_, b1) } = ptr);
+    assert_eq!(a as usize - ptr as usize, offset_of!(Foo, a));
+    assert_eq!(b1 as usize - ptr as usize, offset_of!(Foo, b.1));
+    assert_eq!(c as usize - ptr as usize, offset_of!(Foo, c));
+    // other pointers are projected in place
+    let field: &mut MaybeUninit<u32> = project!((&mut foo) -> b -> 1);
+    assert_eq!(
+        field as *mut _ as usize - foo.as_ptr() as usize,
+        offset_of!(Foo, b.1)
+    );
+
+    fn second<T>() -> usize {
+        offset_of!((u8, T), 1)
+    }
+    assert_eq!(second::<u8>(), 1);
+}
+
+#[test]
+fn test_destructuring_assignment() {
+    struct Node {
+        value: usize,
+        next: Option<Pin<Box<Node>>>,
+        _p: PhantomPinned,
+    }
+    pin_projectable! { Node }
+    fn node(value: usize, next: Option<Pin<Box<Node>>>) -> Pin<Box<Node>> {
+        Box::pin(Node {
+            value,
+            next,
+            _p: PhantomPinned,
+        })
+    }
+
+    let mut list = node(1, Some(node(2, Some(node(3, None)))));
+    let mut cursor: Pin<&mut Node> = list.as_mut();
+    let (mut value, mut next);
+    loop {
+        project!(Node { value, next, .. } = cursor);
+        *value *= 10;
+        match next {
+            Some(node) => cursor = node.as_mut(),
+            None => break,
+        }
+    }
+    assert_eq!(*value, 30);
+    assert_eq!(list.value, 10);
+
+    struct Pair(usize, Box<usize>);
+    let mut pair = Pair(1, Box::new(2));
+    let (first, second);
+    project!(Pair(first, *second) = &mut pair);
+    core::mem::swap(first, second);
+    assert_eq!((pair.0, *pair.1), (2, 1));
+
+    let third;
+    project!([_, _, third] = &[1, 2, 3]);
+    assert_eq!(*third, 3);
+    let (a, b);
+    project!((a, b) = &(4, 5));
+    assert_eq!((*a, *b), (4, 5));
+
+    // unit variants written as paths are not bindings
+    enum Kind {
+        Empty,
+    }
+    struct Tagged {
+        kind: Kind,
+        value: usize,
+    }
+    let mut tagged = Tagged {
+        kind: Kind::Empty,
+        value: 6,
+    };
+    let value;
+    project!(
+        Tagged {
+            kind: Kind::Empty,
+            value
+        } = &mut tagged
+    );
+    *value += 1;
+    assert_eq!(tagged.value, 7);
+}
+
+#[test]
+fn test_project_with() {
+    use projecture::lens::LensTarget;
+    use projecture::{lens, ProjectWith, Unpinned};
+    use std::cell::Cell;
+    use std::mem::MaybeUninit;
+    use std::ptr::NonNull;
+
+    struct Foo {
+        x: usize,
+        y: Bar,
+        u: Unpinned<String>,
+        p: PhantomPinned,
+    }
+    struct Bar(u8, Box<usize>);
+    pin_projectable! { Foo }
+
+    let mut foo = Box::pin(Foo {
+        x: 1,
+        y: Bar(2, Box::new(3)),
+        u: Unpinned("u".to_owned()),
+        p: PhantomPinned,
+    });
+    let p: Pin<&mut PhantomPinned> = foo.as_mut().project_with(lens!(Foo => p)).finish();
+    let _ = p;
+    // `Unpin` fields are unpinned like with `project!`
+    let x: &mut usize = foo.as_mut().project_with(lens!(Foo => x)).finish();
+    *x += 1;
+    let y: &u8 = foo.as_ref().project_with(lens!(Foo => y.0)).finish();
+    assert_eq!(*y, 2);
+    let u: &mut String = foo.as_mut().project_with(lens!(Foo => u)).finish();
+    u.push('!');
+    assert_eq!(foo.u.0, "u!");
+    assert_eq!((foo.x, foo.y.0, *foo.y.1), (2, 2, 3));
+
+    // transparent wrappers are kept on the field
+    let cell = Cell::new((1u8, 2u16));
+    let second: &Cell<u16> = (&cell).project_with(lens!((u8, u16) => 1)).finish();
+    second.set(3);
+    assert_eq!(cell.get(), (1, 3));
+    let mut uninit = MaybeUninit::<(u8, u16)>::uninit();
+    let first: &mut MaybeUninit<u8> = (&mut uninit).project_with(lens!((u8, u16) => 0)).finish();
+    first.write(4);
+
+    // generic helper that works with any kind of pointer
+    fn second_field<P: ProjectWith, Kind>(
+        ptr: P,
+    ) -> <<P::Output as LensTarget<Bar, Kind>>::Marker as ProjectableMarker<Box<usize>>>::Output
+    where
+        P::Output: LensTarget<Bar, Kind>,
+        <P::Output as LensTarget<Bar, Kind>>::Marker: ProjectableMarker<Box<usize>>,
+    {
+        ptr.project_with(lens!(Bar => 1)).finish()
+    }
+    let mut bar = Bar(1, Box::new(2));
+    **second_field(&mut bar) += 1;
+    assert_eq!(**second_field(&bar), 3);
+    let raw: *const Box<usize> = second_field(&bar as *const Bar);
+    assert_eq!(unsafe { **raw }, 3);
+    let non_null: NonNull<Box<usize>> = second_field(NonNull::from(&mut bar));
+    assert_eq!(unsafe { **non_null.as_ptr() }, 3);
+}
+
+#[test]
+#[should_panic(expected = "`Projected::finish` can be called only once")]
+fn test_project_with_finish_twice() {
+    use projecture::{lens, ProjectWith};
+    let mut pair = (1u32, 2u32);
+    let projected = (&mut pair).project_with(lens!((u32, u32) => 0));
+    let first: &mut u32 = projected.finish();
+    let _: &mut u32 = projected.finish();
+    *first += 1;
+}
+
+#[test]
+fn test_lens() {
+    use projecture::{lens, offset_of, Lens};
+
+    #[derive(Clone, Copy, Default)]
+    struct Point {
+        x: u32,
+        y: u32,
+    }
+    #[derive(Clone, Copy, Default)]
+    struct Shape {
+        id: u8,
+        points: [Point; 3],
+    }
+    struct Scene {
+        shapes: (Shape, Shape),
+        _p: PhantomPinned,
+    }
+    pin_projectable! { Scene }
+
+    // fields passed around as data
+    let fields: [Lens<Shape, u32>; 3] = [
+        lens!(Shape => points[0].x),
+        lens!(Shape => points[1].y),
+        lens!(Shape => points)
+            .then(lens!([Point; 3] => [2]))
+            .then(lens!(Point => x)),
+    ];
+    let second = lens!(Scene => shapes.1);
+    assert_eq!(lens!(Shape => id).offset(), offset_of!(Shape, id));
+    assert_eq!(fields[1].offset(), offset_of!(Shape, points) + 12);
+
+    let mut shape = Shape::default();
+    for (i, field) in fields.iter().enumerate() {
+        *field.project(&mut shape) = i as u32 + 1;
+    }
+    assert_eq!(
+        (shape.points[0].x, shape.points[1].y, shape.points[2].x),
+        (1, 2, 3)
+    );
+
+    let cell = Cell::new(shape);
+    fields[0].project(&cell).set(10);
+    assert_eq!(cell.get().points[0].x, 10);
+    let raw: *const u32 = fields[2].project(&shape as *const Shape);
+    assert_eq!(unsafe { *raw }, 3);
+
+    let mut scene = Box::pin(Scene {
+        shapes: (Shape::default(), shape),
+        _p: PhantomPinned,
+    });
+    let x: Pin<&mut u32> = second.then(fields[0]).project(scene.as_mut());
+    *x.get_mut() += 1;
+    assert_eq!(*second.then(fields[0]).project(scene.as_ref().get_ref()), 2);
+    let id: &u8 = second
+        .then(lens!(Shape => id))
+        .project(scene.as_ref().get_ref());
+    assert_eq!(*id, 0);
+}
+
+#[cfg(feature = "macro_rules_attribute")]
+#[test]
+fn test_field_of() {
+    use macro_rules_attribute::derive;
+    use projecture::generic::GenericPointer;
+    use projecture::{generic_ptr, Field, FieldOf};
+
+    #[derive(FieldOf!)]
+    struct Node<T>
+    where
+        T: Copy,
+    {
+        value: T,
+        next: Option<Box<Node<T>>>,
+        link: Link,
+    }
+    #[derive(FieldOf!, Default)]
+    struct Link(usize, usize);
+
+    // works for any field and any pointer kind
+    fn field<T, F, M>(ptr: GenericPointer<T, M>) -> M::Output
+    where
+        F: FieldOf<T>,
+        M: ProjectableMarker<F::Type>,
+    {
+        ptr.field::<F>().into_concrete()
+    }
+    type Value = Field<Node<u8>, 0>;
+    type NextLink = Field<Link, 1>;
+
+    let mut node = Node {
+        value: 1u8,
+        next: None,
+        link: Link::default(),
+    };
+    *field::<_, Value, _>(generic_ptr!(&mut node)) += 1;
+    let value: &u8 = field::<_, Value, _>(generic_ptr!(&node));
+    assert_eq!(*value, 2);
+    let link = generic_ptr!(&mut node).field::<Field<Node<u8>, 2>>();
+    *field::<_, NextLink, _>(link) = 5;
+    assert_eq!(node.link.1, 5);
+    assert!(node.next.is_none());
+
+    let first_link = <Field<Node<u8>, 2> as FieldOf<_>>::lens().then(<Field<Link, 0>>::lens());
+    *first_link.project(&mut node) = 3;
+    assert_eq!(node.link.0, 3);
+    assert_eq!(<Value as FieldOf<_>>::NAME, "value");
+    assert_eq!(<NextLink as FieldOf<_>>::NAME, "1");
+}
+
+#[cfg(feature = "macro_rules_attribute")]
+#[test]
+fn test_pin_fields() {
+    use macro_rules_attribute::apply;
+    use projecture::{CallGuard, PinDrop, PinFields};
+    use std::cell::RefCell;
+    use std::future::Future;
+    use std::rc::Rc;
+    use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    #[apply(PinFields!)]
+    #[derive(Default)]
+    pub struct Counted<F> {
+        #[pin]
+        inner: F,
+        /// not pinned even if `F` is not `Unpin`
+        pub(crate) polls: usize,
+        log: Rc<RefCell<Vec<&'static str>>>,
+    }
+    impl<F: Future> Future for Counted<F> {
+        type Output = F::Output;
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+            project!(let Counted { inner, polls, log } = self);
+            *polls += 1;
+            log.borrow_mut().push("poll");
+            inner.poll(cx)
+        }
+    }
+    impl<F> PinDrop for Counted<F> {
+        fn drop(this: CallGuard<Pin<&mut Self>>) {
+            let this = this.into_inner();
+            let log: &mut Rc<_> = project!(this -> log);
+            log.borrow_mut().push("drop");
+        }
+    }
+
+    #[apply(PinFields!)]
+    struct Pair<T>(T, #[unpin] Box<usize>, #[unpin] PhantomPinned)
+    where
+        T: Debug;
+
+    fn assert_unpin<T: Unpin>() {}
+    assert_unpin::<Counted<std::future::Ready<()>>>();
+
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(|_| RAW, |_| {}, |_| {}, |_| {});
+    const RAW: RawWaker = RawWaker::new(std::ptr::null(), &VTABLE);
+    let waker = unsafe { Waker::from_raw(RAW) };
+    let mut cx = Context::from_waker(&waker);
+    let log = Rc::new(RefCell::new(vec![]));
+    let mut fut = Box::pin(Counted {
+        inner: async {},
+        polls: 0,
+        log: log.clone(),
+    });
+    assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready(()));
+    let fut_ref = fut.as_ref();
+    let polls: &usize = project!(fut_ref -> polls);
+    assert_eq!(*polls, 1);
+    let _: Pin<&_> = project!(fut_ref -> inner);
+    assert_eq!(*log.borrow(), ["poll"]);
+    drop(fut);
+    // `Drop` generated by `PinFields!` calls `PinDrop::drop`
+    assert_eq!(*log.borrow(), ["poll", "drop"]);
+
+    let mut pair = Box::pin(Pair(PhantomPinned, Box::new(2), PhantomPinned));
+    project!(let Pair(first, second, marker) = pair.as_mut());
+    let _: Pin<&mut PhantomPinned> = first;
+    let _: &mut PhantomPinned = marker;
+    **second = 3;
+    assert_eq!(*pair.1, 3);
+    let pair = pair.as_mut();
+    let _: &mut PhantomPinned = project!(pair -> 2);
+    let counted: Counted<()> = Default::default();
+    assert_eq!(counted.polls, 0);
+}
+
+#[cfg(feature = "macro_rules_attribute")]
+#[test]
+fn test_pin_replace() {
+    use macro_rules_attribute::{apply, derive};
+    use projecture::pin::replace;
+    use projecture::{CallGuard, Fields, PinDrop, PinFields, PinProjectable, Unpinned};
+    use std::cell::RefCell;
+    use std::marker::PhantomData;
+    use std::rc::Rc;
+
+    struct Logged(&'static str, Rc<RefCell<Vec<&'static str>>>);
+    impl Drop for Logged {
+        fn drop(&mut self) {
+            self.1.borrow_mut().push(self.0);
+        }
+    }
+
+    #[apply(PinFields!)]
+    struct State {
+        #[pin]
+        pinned: Logged,
+        moved: Logged,
+        unmentioned: Logged,
+        _p: PhantomPinned,
+    }
+    impl PinDrop for State {
+        fn drop(this: CallGuard<Pin<&mut Self>>) {
+            let this = this.into_inner();
+            project!(let State { moved, .. } = this);
+            moved.1.borrow_mut().push("pin drop");
+        }
+    }
+
+    let log = Rc::new(RefCell::new(vec![]));
+    let state = |name| State {
+        pinned: Logged(name, log.clone()),
+        moved: Logged("moved", log.clone()),
+        unmentioned: Logged("unmentioned", log.clone()),
+        _p: PhantomPinned,
+    };
+    let mut pinned = Box::pin(state("first"));
+    project!(let State { pinned: first, moved, .. } = replace(pinned.as_mut(), state("second")));
+    let _: PhantomData<Logged> = first;
+    assert_eq!(*log.borrow(), ["pin drop", "unmentioned", "first"]);
+    drop(moved);
+    assert_eq!(log.borrow().last(), Some(&"moved"));
+    assert_eq!(pinned.pinned.0, "second");
+    log.borrow_mut().clear();
+
+    // dropped as a whole if not projected
+    drop(replace(pinned.as_mut(), state("third")));
+    assert_eq!(
+        *log.borrow(),
+        ["pin drop", "second", "moved", "unmentioned"]
+    );
+    log.borrow_mut().clear();
+
+    #[derive(Fields!, PinProjectable!)]
+    struct Tuple(Logged, Unpinned<Logged>);
+    let mut tuple = Box::pin(Tuple(
+        Logged("a", log.clone()),
+        Unpinned(Logged("b", log.clone())),
+    ));
+    project!(let Tuple(_, b) = replace(tuple.as_mut(), Tuple(Logged("c", log.clone()), Unpinned(Logged("d", log.clone())))));
+    assert_eq!(*log.borrow(), ["a"]);
+    assert_eq!(b.0 .0, "b");
+    drop(b);
+    drop(tuple);
+    assert_eq!(*log.borrow(), ["a", "b", "c", "d"]);
+    log.borrow_mut().clear();
+
+    // calling `get_raw` directly doesn't run `PinDrop` or move the new value out
+    let mut pinned = Box::pin(state("first"));
+    let replaced = ManuallyDrop::new(replace(pinned.as_mut(), state("second")));
+    let wrapped = {
+        use projecture::Preprocess;
+        (&&replaced).preprocess()
+    };
+    let (_, marker) = wrapped.get_raw();
+    let (_, marker2) = wrapped.get_raw();
+    let _ = (marker, marker2);
+    assert!(log.borrow().is_empty());
+    drop(wrapped);
+    assert_eq!(*log.borrow(), ["pin drop", "first", "moved", "unmentioned"]);
+    assert_eq!(pinned.pinned.0, "second");
+}
+
+#[cfg(feature = "macro_rules_attribute")]
+#[test]
+fn test_pin_replace_unwind() {
+    use macro_rules_attribute::apply;
+    use projecture::pin::replace;
+    use projecture::{CallGuard, PinDrop, PinFields};
+    use std::cell::{Cell, RefCell};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    struct Bomb(bool);
+    impl Drop for Bomb {
+        fn drop(&mut self) {
+            if self.0 {
+                panic!("bomb");
+            }
+        }
+    }
+    struct Logged(&'static str, Rc<RefCell<Vec<&'static str>>>);
+    impl Drop for Logged {
+        fn drop(&mut self) {
+            self.1.borrow_mut().push(self.0);
+        }
+    }
+
+    #[apply(PinFields!)]
+    struct State {
+        #[pin]
+        bomb: Bomb,
+        #[pin]
+        pinned: Logged,
+        moved: Logged,
+        panic_in_pin_drop: Cell<bool>,
+    }
+    impl PinDrop for State {
+        fn drop(this: CallGuard<Pin<&mut Self>>) {
+            if this.into_inner().panic_in_pin_drop.get() {
+                panic!("pin drop");
+            }
+        }
+    }
+
+    let log = Rc::new(RefCell::new(vec![]));
+    let state = |name, bomb| State {
+        bomb: Bomb(bomb),
+        pinned: Logged(name, log.clone()),
+        moved: Logged("moved", log.clone()),
+        panic_in_pin_drop: Cell::new(false),
+    };
+    let mut pinned = Box::pin(state("first", true));
+
+    // unmentioned fields are all dropped even though one of them panics, and only then the new value is written
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        project!(let State { moved, .. } = replace(pinned.as_mut(), state("second", true)));
+        moved
+    }));
+    assert!(result.is_err());
+    assert_eq!(*log.borrow(), ["first", "moved"]);
+    assert_eq!(pinned.pinned.0, "second");
+    log.borrow_mut().clear();
+
+    // pinned fields from the pattern are dropped on unwind
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        project!(let State { pinned: old, moved, .. } = replace(pinned.as_mut(), state("third", true)));
+        (old, moved)
+    }));
+    assert!(result.is_err());
+    assert_eq!(*log.borrow(), ["moved", "second"]);
+    assert_eq!(pinned.pinned.0, "third");
+    log.borrow_mut().clear();
+
+    // `bomb` is dropped first when the projection is finished, then the other pinned field
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        project!(let State { pinned: old, bomb, moved, .. } = replace(pinned.as_mut(), state("fourth", false)));
+        (old, bomb, moved)
+    }));
+    assert!(result.is_err());
+    assert_eq!(*log.borrow(), ["third", "moved"]);
+    assert_eq!(pinned.pinned.0, "fourth");
+    log.borrow_mut().clear();
+
+    // panic in `PinDrop`
+    pinned.panic_in_pin_drop.set(true);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        project!(let State { moved, .. } = replace(pinned.as_mut(), state("fifth", false)));
+        moved
+    }));
+    assert!(result.is_err());
+    assert_eq!(*log.borrow(), ["fourth", "moved"]);
+    assert_eq!(pinned.pinned.0, "fifth");
+    log.borrow_mut().clear();
+
+    // dropped as a whole
+    drop(replace(pinned.as_mut(), state("sixth", false)));
+    assert_eq!(*log.borrow(), ["fifth", "moved"]);
+    pinned.panic_in_pin_drop.set(true);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        drop(replace(pinned.as_mut(), state("seventh", false)));
+    }));
+    assert!(result.is_err());
+    assert_eq!(*log.borrow(), ["fifth", "moved", "sixth", "moved"]);
+    assert_eq!(pinned.pinned.0, "seventh");
+}
+
+#[cfg(feature = "macro_rules_attribute")]
+#[test]
+fn test_pin_enum() {
+    use macro_rules_attribute::derive;
+    use projecture::pin::replace;
+    use projecture::{CallGuard, Fields, PinDrop, PinProjectable, Unpinned};
+    use std::future::Future;
+    use std::marker::PhantomData;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    #[derive(Fields!, PinProjectable!)]
+    enum Map<Fut, F>
+    where
+        Fut: Future,
+    {
+        Incomplete { fut: Fut, f: Unpinned<Option<F>> },
+        Polled(Fut, Unpinned<usize>),
+        Complete,
+    }
+    impl<Fut: Future, F: FnOnce(Fut::Output) -> T, T> Future for Map<Fut, F> {
+        type Output = T;
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+            let output = project!(match self.as_mut() {
+                Map::Incomplete { fut, f } => match fut.poll(cx) {
+                    Poll::Ready(output) => f.take().unwrap()(output),
+                    Poll::Pending => return Poll::Pending,
+                },
+                Map::Polled(_, polls) => {
+                    let _: &mut usize = polls;
+                    return Poll::Pending;
+                }
+                Map::Complete => panic!("polled after completion"),
+            });
+            self.set(Map::Complete);
+            Poll::Ready(output)
+        }
+    }
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+    impl<Fut: Future, F> PinDrop for Map<Fut, F> {
+        fn drop(this: CallGuard<Pin<&mut Self>>) {
+            let this = this.into_inner();
+            project!(if let Map::Incomplete { fut, .. } = this {
+                let _fut: Pin<&mut Fut> = fut;
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+    }
+
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(|_| RAW, |_| {}, |_| {}, |_| {});
+    const RAW: RawWaker = RawWaker::new(std::ptr::null(), &VTABLE);
+    let waker = unsafe { Waker::from_raw(RAW) };
+    let mut cx = Context::from_waker(&waker);
+    async fn two() -> i32 {
+        2
+    }
+    let new = || Map::Incomplete {
+        fut: two(),
+        f: Unpinned(Some(|x| x * 3)),
+    };
+    let mut map = Box::pin(new());
+    assert_eq!(map.as_mut().poll(&mut cx), Poll::Ready(6));
+    assert!(matches!(*map, Map::Complete));
+    // `set` dropped the incomplete state
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 1);
+    drop(Box::pin(new()));
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 2);
+
+    let map_ref = map.as_ref();
+    project!(let Map::Complete = map_ref else { unreachable!() });
+
+    let mut map = Box::pin(new());
+    let f = project!(
+        match replace(map.as_mut(), Map::Polled(two(), Unpinned(1))) {
+            Map::Incomplete { fut, f } => {
+                let _: PhantomData<_> = fut;
+                f.0
+            }
+            _ => None,
+        }
+    );
+    assert_eq!(f.unwrap()(1), 3);
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 3);
+    assert_eq!(map.as_mut().poll(&mut cx), Poll::Pending);
+    let polls = project!(match replace(map.as_mut(), new()) {
+        Map::Polled(_, polls) => polls.0,
+        _ => 0,
+    });
+    assert_eq!(polls, 1);
+    // not destructured variant is dropped as a whole
+    project!(match replace(map.as_mut(), Map::Complete) {
+        Map::Complete => unreachable!(),
+        _ => {}
+    });
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 4);
+}
+
+#[cfg(feature = "macro_rules_attribute")]
+#[test]
+fn test_pin_shared() {
+    use macro_rules_attribute::{apply, derive};
+    use projecture::{PinFields, PinProjectable, Unpinned};
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[derive(PinProjectable!)]
+    struct Task {
+        fut: PhantomPinned,
+        id: Unpinned<usize>,
+    }
+    #[derive(PinProjectable!)]
+    enum State {
+        Running(PhantomPinned),
+        Done(Unpinned<usize>),
+    }
+    struct Executor {
+        current: Pin<Rc<Task>>,
+    }
+    #[apply(PinFields!)]
+    struct Slot {
+        #[pin]
+        fut: PhantomPinned,
+        polls: usize,
+    }
+
+    let task = Arc::pin(Task {
+        fut: PhantomPinned,
+        id: Unpinned(1),
+    });
+    let _: Pin<&PhantomPinned> = project!((&task) -> fut);
+    project!(let Task { fut, id } = &task);
+    let _: Pin<&PhantomPinned> = fut;
+    assert_eq!(*id, 1);
+
+    let executor = Executor {
+        current: Rc::pin(Task {
+            fut: PhantomPinned,
+            id: Unpinned(2),
+        }),
+    };
+    project!(let Executor { current: *Task { fut, id } } = &executor);
+    let _: Pin<&PhantomPinned> = fut;
+    assert_eq!(*id, 2);
+
+    let result = |state: &Pin<Rc<State>>| {
+        project!(match state {
+            State::Running(fut) => {
+                let _: Pin<&PhantomPinned> = fut;
+                None
+            }
+            State::Done(result) => Some(*result),
+        })
+    };
+    assert_eq!(result(&Rc::pin(State::Running(PhantomPinned))), None);
+    assert_eq!(result(&Rc::pin(State::Done(Unpinned(3)))), Some(3));
+
+    let slot = Arc::pin(Slot {
+        fut: PhantomPinned,
+        polls: 4,
+    });
+    project!(let Slot { fut, polls } = &slot);
+    let _: Pin<&PhantomPinned> = fut;
+    assert_eq!(*polls, 4);
+}
+
+#[cfg(feature = "macro_rules_attribute")]
+#[test]
+fn test_pin_init() {
+    use macro_rules_attribute::derive;
+    use projecture::pin::{init_box, write, Unpinned};
+    use projecture::{pin_init, CallGuard, PinDrop, PinProjectable};
+    use std::mem::MaybeUninit;
+
+    // registers itself in the list on creation, and unregisters on drop
+    #[derive(PinProjectable!)]
+    struct Waiter<'a> {
+        list: &'a Cell<Vec<*const Waiter<'a>>>,
+        id: Unpinned<usize>,
+        _pin: PhantomPinned,
+    }
+    impl<'a> PinDrop for Waiter<'a> {
+        fn drop(this: CallGuard<Pin<&mut Self>>) {
+            let this = this.into_inner();
+            let ptr = &*this as *const Waiter;
+            let mut list = this.list.take();
+            list.retain(|&waiter| waiter != ptr);
+            this.list.set(list);
+        }
+    }
+    #[derive(PinProjectable!)]
+    struct Pair<'a>(Waiter<'a>, usize);
+
+    fn init<'a, 'b>(
+        slot: Pin<&'b mut MaybeUninit<Waiter<'a>>>,
+        list: &'a Cell<Vec<*const Waiter<'a>>>,
+        id: usize,
+    ) -> projecture::pin::Written<'b, Waiter<'a>> {
+        let mut waiters = list.take();
+        waiters.push(slot.as_ptr());
+        list.set(waiters);
+        project!(let Waiter { list: list_slot, id: id_slot, _pin } = slot);
+        let id_slot: &mut MaybeUninit<Unpinned<usize>> = id_slot;
+        pin_init!(Waiter {
+            list: write(list_slot, list),
+            id: write(id_slot, Unpinned(id)),
+            _pin: write(_pin, PhantomPinned),
+        })
+    }
+
+    let list = Cell::new(vec![]);
+    let len = |list: &Cell<Vec<_>>| {
+        let waiters = list.take();
+        let len = waiters.len();
+        list.set(waiters);
+        len
+    };
+    {
+        let mut slot = MaybeUninit::<Pair>::uninit();
+        let mut slot = unsafe { Pin::new_unchecked(&mut slot) };
+        project!(let Pair(waiter, value) = slot.as_mut());
+        let waiter = init(waiter, &list, 0);
+        pin_init!(let mut pair = slot => Pair { 0: waiter, 1: write(value, 5) });
+        assert_eq!(len(&list), 1);
+        let pair_mut = pair.as_mut();
+        let (waiter, value) = project!(pair_mut -> {0, 1});
+        assert_eq!(*project!(waiter -> id), 0);
+        *value += 1;
+        assert_eq!(pair.1, 6);
+    }
+    assert_eq!(len(&list), 0);
+
+    // value in a slot that is not pinned is only mutably borrowed
+    {
+        let mut slot = MaybeUninit::<Waiter>::uninit();
+        project!(let Waiter { list: list_slot, id, _pin } = &mut slot);
+        pin_init!(let waiter = slot => Waiter {
+            list: write(list_slot, &list),
+            id: write(id, Unpinned(2)),
+            _pin: write(_pin, PhantomPinned),
+        });
+        let waiter: &mut Waiter = waiter;
+        assert_eq!(*waiter.id, 2);
+    }
+
+    let waiter = init_box(Box::pin(MaybeUninit::uninit()), |slot| init(slot, &list, 1));
+    assert_eq!(list.take(), [&*waiter as *const Waiter]);
+}
+
+#[test]
+#[should_panic(expected = "`pin_init!` got fields that were written to different places")]
+fn test_pin_init_mismatched() {
+    use projecture::pin::write;
+    use projecture::pin_init;
+    use std::mem::MaybeUninit;
+
+    struct Pair(usize, usize);
+    let mut first = MaybeUninit::<Pair>::uninit();
+    let mut second = MaybeUninit::<Pair>::uninit();
+    project!(let Pair(a, _) = &mut first);
+    project!(let Pair(_, b) = &mut second);
+    pin_init!(let _pair = first => Pair { 0: write(a, 1), 1: write(b, 2) });
+}
+
+#[test]
+#[should_panic(expected = "`pin_init!` got proof for some other value")]
+fn test_pin_init_other_slot() {
+    use projecture::pin::write;
+    use projecture::pin_init;
+    use std::mem::MaybeUninit;
+
+    struct Pair(usize, usize);
+    let mut first = MaybeUninit::<Pair>::uninit();
+    let mut second = MaybeUninit::<Pair>::uninit();
+    project!(let Pair(a, b) = &mut second);
+    pin_init!(let _pair = first => Pair { 0: write(a, 1), 1: write(b, 2) });
+}
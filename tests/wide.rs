@@ -0,0 +1,123 @@
+use projecture::project;
+use std::cell::Cell;
+
+#[test]
+fn test_wide_tuple() {
+    struct Wide(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, String);
+    let mut wide = Wide(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, "13".to_string());
+    project!(let Wide(_, _, _, _, _, _, _, _, _, _, _, x, y, s) = &mut wide);
+    *x += 1;
+    *y += 1;
+    s.push('!');
+    assert_eq!((wide.11, wide.12, wide.13.as_str()), (12, 13, "13!"));
+    *project!((&mut wide) -> 12) += 1;
+    project!(let Wide { 12: y, 13: s } = &wide);
+    assert_eq!((*y, s.as_str()), (14, "13!"));
+
+    let cell = Cell::new(Wide(
+        0,
+        1,
+        2,
+        3,
+        4,
+        5,
+        6,
+        7,
+        8,
+        9,
+        10,
+        11,
+        12,
+        String::new(),
+    ));
+    project!((&cell) -> 12).set(20);
+    assert_eq!(project!((&cell) -> 12).get(), 20);
+}
+
+#[test]
+fn test_wide_packed() {
+    #[repr(C, packed)]
+    struct Packed(
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u32,
+    );
+    let packed = Packed(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+    let ptr = &packed as *const Packed;
+    let val = unsafe { project!(ptr -> 15).read_unaligned() };
+    assert_eq!(val, 15);
+    project!(let Packed(_, _, _, _, _, _, _, _, _, _, _, _, _, x, ..) = ptr);
+    assert_eq!(unsafe { x.read() }, 13);
+}
+
+#[allow(dead_code)]
+#[rustfmt::skip]
+struct Huge(u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16, u16);
+
+#[test]
+fn test_more_than_256_fields() {
+    #[rustfmt::skip]
+    let mut huge = Huge(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 72, 73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 88, 89, 90, 91, 92, 93, 94, 95, 96, 97, 98, 99, 100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110, 111, 112, 113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125, 126, 127, 128, 129, 130, 131, 132, 133, 134, 135, 136, 137, 138, 139, 140, 141, 142, 143, 144, 145, 146, 147, 148, 149, 150, 151, 152, 153, 154, 155, 156, 157, 158, 159, 160, 161, 162, 163, 164, 165, 166, 167, 168, 169, 170, 171, 172, 173, 174, 175, 176, 177, 178, 179, 180, 181, 182, 183, 184, 185, 186, 187, 188, 189, 190, 191, 192, 193, 194, 195, 196, 197, 198, 199, 200, 201, 202, 203, 204, 205, 206, 207, 208, 209, 210, 211, 212, 213, 214, 215, 216, 217, 218, 219, 220, 221, 222, 223, 224, 225, 226, 227, 228, 229, 230, 231, 232, 233, 234, 235, 236, 237, 238, 239, 240, 241, 242, 243, 244, 245, 246, 247, 248, 249, 250, 251, 252, 253, 254, 255, 256, 257, 258, 259, 260, 261, 262, 263, 264, 265, 266, 267, 268, 269, 270, 271, 272, 273, 274, 275, 276, 277, 278, 279, 280, 281, 282, 283, 284, 285, 286, 287, 288, 289, 290, 291, 292, 293, 294, 295, 296, 297, 298, 299);
+    #[rustfmt::skip]
+    project!(let Huge(_, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, _, x, ..) = &mut huge);
+    *x += 1;
+    assert_eq!(huge.270, 271);
+    project!(let Huge(.., y, _) = &huge);
+    assert_eq!(*y, 298);
+}
+
+#[test]
+fn test_wide_struct() {
+    #[rustfmt::skip]
+    struct Wide { f0: u8, f1: u8, f2: u8, f3: u8, f4: u8, f5: u8, f6: u8, f7: u8, f8: u8, f9: u8, f10: u8, f11: u8, f12: u8, f13: u8, f14: u8, f15: u8, f16: u8, f17: u8, f18: u8, f19: u8, f20: u8, f21: u8, f22: u8, f23: u8, f24: u8, f25: u8, f26: u8, f27: u8, f28: u8, f29: u8, f30: u8, f31: u8, f32: u8, f33: u8, f34: u8, f35: u8, f36: u8, f37: u8, f38: u8, f39: u8, f40: u8, f41: u8, f42: u8, f43: u8, f44: u8, f45: u8, f46: u8, f47: u8, f48: u8, f49: u8, f50: u8, f51: u8, f52: u8, f53: u8, f54: u8, f55: u8, f56: u8, f57: u8, f58: u8, f59: u8, f60: u8, f61: u8, f62: u8, last: Box<u32> }
+    #[rustfmt::skip]
+    let mut wide = Wide { f0: 0, f1: 1, f2: 2, f3: 3, f4: 4, f5: 5, f6: 6, f7: 7, f8: 8, f9: 9, f10: 10, f11: 11, f12: 12, f13: 13, f14: 14, f15: 15, f16: 16, f17: 17, f18: 18, f19: 19, f20: 20, f21: 21, f22: 22, f23: 23, f24: 24, f25: 25, f26: 26, f27: 27, f28: 28, f29: 29, f30: 30, f31: 31, f32: 32, f33: 33, f34: 34, f35: 35, f36: 36, f37: 37, f38: 38, f39: 39, f40: 40, f41: 41, f42: 42, f43: 43, f44: 44, f45: 45, f46: 46, f47: 47, f48: 48, f49: 49, f50: 50, f51: 51, f52: 52, f53: 53, f54: 54, f55: 55, f56: 56, f57: 57, f58: 58, f59: 59, f60: 60, f61: 61, f62: 62, last: Box::new(63) };
+    #[rustfmt::skip]
+    project!(let Wide { f0, f1, f2, f3, f4, f5, f6, f7, f8, f9, f10, f11, f12, f13, f14, f15, f16, f17, f18, f19, f20, f21, f22, f23, f24, f25, f26, f27, f28, f29, f30, f31, f32, f33, f34, f35, f36, f37, f38, f39, f40, f41, f42, f43, f44, f45, f46, f47, f48, f49, f50, f51, f52, f53, f54, f55, f56, f57, f58, f59, f60, f61, f62, last: l } = &mut wide);
+    *f62 += 1;
+    *f0 += 1;
+    **l += 1;
+    assert_eq!((wide.f0, wide.f62, *wide.last), (1, 63, 64));
+
+    #[rustfmt::skip]
+    project!(let Wide { f0, f1, f2, f3, f4, f5, f6, f7, f8, f9, f10, f11, f12, f13, f14, f15, f16, f17, f18, f19, f20, f21, f22, f23, f24, f25, f26, f27, f28, f29, f30, f31, f32, f33, f34, f35, f36, f37, f38, f39, f61: x, last: *l, .. } = &wide);
+    assert_eq!((*f39, *x, *l), (39, 61, 64));
+}
+
+struct Inner {
+    value: u8,
+    other: u8,
+}
+
+struct Pair(u8, u8);
+
+#[test]
+fn test_wide_struct_nested() {
+    #[rustfmt::skip]
+    struct Wide { f0: Inner, f1: Inner, f2: Inner, f3: Inner, f4: Inner, f5: Inner, f6: Inner, f7: Inner, f8: Inner, f9: Inner, f10: Inner, f11: Inner, f12: Inner, f13: Inner, f14: Inner, f15: Inner, f16: Inner, f17: Inner, f18: Inner, f19: Inner, f20: Inner, f21: Inner, f22: Inner, f23: Inner, f24: Inner, f25: Inner, f26: Inner, f27: Inner, f28: Inner, f29: Inner, pair: Pair, array: [u8; 2] }
+    #[rustfmt::skip]
+    let mut wide = Wide { f0: Inner { value: 0, other: 0 }, f1: Inner { value: 1, other: 0 }, f2: Inner { value: 2, other: 0 }, f3: Inner { value: 3, other: 0 }, f4: Inner { value: 4, other: 0 }, f5: Inner { value: 5, other: 0 }, f6: Inner { value: 6, other: 0 }, f7: Inner { value: 7, other: 0 }, f8: Inner { value: 8, other: 0 }, f9: Inner { value: 9, other: 0 }, f10: Inner { value: 10, other: 0 }, f11: Inner { value: 11, other: 0 }, f12: Inner { value: 12, other: 0 }, f13: Inner { value: 13, other: 0 }, f14: Inner { value: 14, other: 0 }, f15: Inner { value: 15, other: 0 }, f16: Inner { value: 16, other: 0 }, f17: Inner { value: 17, other: 0 }, f18: Inner { value: 18, other: 0 }, f19: Inner { value: 19, other: 0 }, f20: Inner { value: 20, other: 0 }, f21: Inner { value: 21, other: 0 }, f22: Inner { value: 22, other: 0 }, f23: Inner { value: 23, other: 0 }, f24: Inner { value: 24, other: 0 }, f25: Inner { value: 25, other: 0 }, f26: Inner { value: 26, other: 0 }, f27: Inner { value: 27, other: 0 }, f28: Inner { value: 28, other: 0 }, f29: Inner { value: 29, other: 0 }, pair: Pair(30, 31), array: [32, 33] };
+    #[rustfmt::skip]
+    project!(let Wide { f0: self::Inner { value: v0, other: _ }, f1: Inner { value: v1, .. }, f2: Inner { value: v2, .. }, f3: _, f4: Inner { value: v4, .. }, f5: Inner { value: v5, .. }, f6: self::Inner { value: v6, other: _ }, f7: Inner { value: v7, .. }, f8: Inner { value: v8, .. }, f9: _, f10: Inner { value: v10, .. }, f11: Inner { value: v11, .. }, f12: self::Inner { value: v12, other: _ }, f13: Inner { value: v13, .. }, f14: Inner { value: v14, .. }, f15: _, f16: Inner { value: v16, .. }, f17: Inner { value: v17, .. }, f18: self::Inner { value: v18, other: _ }, f19: Inner { value: v19, .. }, f20: Inner { value: v20, .. }, f21: _, f22: Inner { value: v22, .. }, f23: Inner { value: v23, .. }, f24: self::Inner { value: v24, other: _ }, f25: Inner { value: v25, .. }, f26: Inner { value: v26, .. }, f27: _, f28: Inner { value: v28, .. }, f29: Inner { value: v29, .. }, pair: Pair(a, _), array: [_, b] } = &mut wide);
+    *v0 += 1;
+    *v29 += 1;
+    *a += 1;
+    *b += 1;
+    assert_eq!(
+        (wide.f0.value, wide.f29.value, wide.pair.0, wide.array[1]),
+        (1, 30, 31, 34)
+    );
+}